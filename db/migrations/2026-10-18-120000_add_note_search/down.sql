@@ -0,0 +1,4 @@
+DROP TRIGGER note_search_update ON notes;
+DROP FUNCTION note_search_update();
+DROP FUNCTION note_search_document(TEXT, TEXT);
+DROP TABLE note_search;
@@ -0,0 +1,33 @@
+CREATE TABLE note_search (
+  note_id int PRIMARY KEY REFERENCES notes(id) ON DELETE CASCADE,
+  document TSVECTOR NOT NULL
+);
+
+CREATE INDEX note_search_document_idx ON note_search USING GIN (document);
+
+CREATE OR REPLACE FUNCTION note_search_document(title TEXT, body TEXT) RETURNS TSVECTOR AS $$
+BEGIN
+    RETURN setweight(to_tsvector('english', coalesce(title, '')), 'A') ||
+           setweight(to_tsvector('english', coalesce(body, '')), 'B');
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION note_search_update() RETURNS trigger AS $$
+BEGIN
+    IF (TG_OP = 'INSERT') THEN
+        INSERT INTO note_search(note_id, document)
+        VALUES (NEW.id, note_search_document(NEW.title, NEW.body));
+    ELSIF (NEW.title IS DISTINCT FROM OLD.title OR NEW.body IS DISTINCT FROM OLD.body) THEN
+        UPDATE note_search
+        SET document = note_search_document(NEW.title, NEW.body)
+        WHERE note_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER note_search_update AFTER INSERT OR UPDATE ON notes
+FOR EACH ROW EXECUTE PROCEDURE note_search_update();
+
+INSERT INTO note_search(note_id, document)
+SELECT id, note_search_document(title, body) FROM notes WHERE id <> 0;
@@ -0,0 +1,3 @@
+DROP TRIGGER note_revisions_record ON notes;
+DROP FUNCTION note_revisions_record();
+DROP TABLE note_revisions;
@@ -0,0 +1,29 @@
+CREATE TABLE note_revisions (
+  id SERIAL PRIMARY KEY,
+  note_id int NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+  title VARCHAR NOT NULL,
+  body TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX note_revisions_note_id_idx ON note_revisions(note_id);
+
+CREATE OR REPLACE FUNCTION note_revisions_record() RETURNS trigger AS $$
+BEGIN
+    IF (
+        TG_OP = 'INSERT' OR
+        NEW.title IS DISTINCT FROM OLD.title OR
+        NEW.body IS DISTINCT FROM OLD.body
+    ) THEN
+        INSERT INTO note_revisions(note_id, title, body)
+        VALUES (NEW.id, NEW.title, NEW.body);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER note_revisions_record AFTER INSERT OR UPDATE ON notes
+FOR EACH ROW EXECUTE PROCEDURE note_revisions_record();
+
+INSERT INTO note_revisions(note_id, title, body, created_at)
+SELECT id, title, body, updated_at FROM notes WHERE id <> 0;
@@ -0,0 +1,9 @@
+DELETE FROM notes WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE note_tags_id
+  DROP CONSTRAINT note_tags_id_note_id_fkey,
+  ADD CONSTRAINT note_tags_id_note_id_fkey
+    FOREIGN KEY (note_id) REFERENCES notes(id);
+
+ALTER TABLE notes
+  DROP COLUMN deleted_at;
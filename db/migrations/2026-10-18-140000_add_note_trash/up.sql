@@ -0,0 +1,10 @@
+ALTER TABLE notes
+  ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX notes_user_id_deleted_at_idx ON notes(user_id, deleted_at);
+
+-- Purging a note from the trash should take its tags with it.
+ALTER TABLE note_tags_id
+  DROP CONSTRAINT note_tags_id_note_id_fkey,
+  ADD CONSTRAINT note_tags_id_note_id_fkey
+    FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE;
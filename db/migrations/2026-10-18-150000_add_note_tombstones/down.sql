@@ -0,0 +1,4 @@
+DROP INDEX notes_user_id_updated_at_idx;
+DROP TRIGGER note_tombstones_record ON notes;
+DROP FUNCTION note_tombstones_record();
+DROP TABLE note_tombstones;
@@ -0,0 +1,20 @@
+CREATE TABLE note_tombstones (
+  id SERIAL PRIMARY KEY,
+  note_id int NOT NULL,
+  user_id int NOT NULL,
+  deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX note_tombstones_user_id_deleted_at_idx ON note_tombstones(user_id, deleted_at);
+
+CREATE OR REPLACE FUNCTION note_tombstones_record() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO note_tombstones(note_id, user_id) VALUES (OLD.id, OLD.user_id);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER note_tombstones_record AFTER DELETE ON notes
+FOR EACH ROW EXECUTE PROCEDURE note_tombstones_record();
+
+CREATE INDEX notes_user_id_updated_at_idx ON notes(user_id, updated_at);
@@ -0,0 +1,5 @@
+DROP TRIGGER notes_bump_version ON notes;
+DROP FUNCTION notes_bump_version();
+
+ALTER TABLE notes
+  DROP COLUMN version;
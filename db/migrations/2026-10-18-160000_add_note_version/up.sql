@@ -0,0 +1,17 @@
+ALTER TABLE notes
+  ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION notes_bump_version() RETURNS trigger AS $$
+BEGIN
+    IF (
+        NEW IS DISTINCT FROM OLD AND
+        NEW.version IS NOT DISTINCT FROM OLD.version
+    ) THEN
+        NEW.version := OLD.version + 1;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notes_bump_version BEFORE UPDATE ON notes
+FOR EACH ROW EXECUTE PROCEDURE notes_bump_version();
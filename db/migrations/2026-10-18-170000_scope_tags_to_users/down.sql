@@ -0,0 +1,13 @@
+-- Fold every user's copy of a tag back into a single global one.
+UPDATE note_tags_id
+SET tag_id = canonical.id
+FROM tags, (SELECT MIN(id) AS id, tag FROM tags GROUP BY tag) AS canonical
+WHERE tags.id = note_tags_id.tag_id
+  AND canonical.tag = tags.tag;
+
+DELETE FROM tags WHERE id NOT IN (SELECT MIN(id) FROM tags GROUP BY tag);
+
+ALTER TABLE tags
+  DROP CONSTRAINT tags_user_id_tag_key,
+  DROP COLUMN user_id,
+  ADD CONSTRAINT tags_tag_key UNIQUE (tag);
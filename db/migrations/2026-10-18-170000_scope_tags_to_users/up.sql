@@ -0,0 +1,24 @@
+ALTER TABLE tags
+  DROP CONSTRAINT tags_tag_key,
+  ADD COLUMN user_id int REFERENCES users(id) ON DELETE CASCADE;
+
+-- Give every user their own copy of each tag they use, then point their notes at it.
+INSERT INTO tags (tag, user_id)
+SELECT DISTINCT tags.tag, notes.user_id
+FROM note_tags_id
+INNER JOIN tags ON tags.id = note_tags_id.tag_id
+INNER JOIN notes ON notes.id = note_tags_id.note_id;
+
+UPDATE note_tags_id
+SET tag_id = scoped.id
+FROM tags AS global, notes, tags AS scoped
+WHERE global.id = note_tags_id.tag_id
+  AND notes.id = note_tags_id.note_id
+  AND scoped.user_id = notes.user_id
+  AND scoped.tag = global.tag;
+
+DELETE FROM tags WHERE user_id IS NULL;
+
+ALTER TABLE tags
+  ALTER COLUMN user_id SET NOT NULL,
+  ADD CONSTRAINT tags_user_id_tag_key UNIQUE (user_id, tag);
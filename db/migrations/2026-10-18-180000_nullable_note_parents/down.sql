@@ -0,0 +1,18 @@
+-- Trashed notes can share titles with live ones, which the old constraint doesn't allow. Reverting
+-- the trash deletes them anyway.
+DELETE FROM notes WHERE deleted_at IS NOT NULL;
+
+DROP INDEX notes_title_user_id_top_level;
+DROP INDEX notes_title_user_id_parent_note_id;
+
+INSERT INTO users (id, name, email, hashed_password) VALUES (0, '', '', '');
+INSERT INTO notes (id, title, body, user_id, parent_note_id) VALUES (0, '', '', 0, 0);
+DELETE FROM note_search WHERE note_id = 0;
+DELETE FROM note_revisions WHERE note_id = 0;
+
+UPDATE notes SET parent_note_id = 0 WHERE parent_note_id IS NULL;
+
+ALTER TABLE notes
+  ALTER COLUMN parent_note_id SET DEFAULT 0,
+  ALTER COLUMN parent_note_id SET NOT NULL,
+  ADD CONSTRAINT notes_title_user_id_parent_note_id UNIQUE(title, user_id, parent_note_id);
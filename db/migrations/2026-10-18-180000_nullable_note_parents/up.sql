@@ -0,0 +1,22 @@
+-- Top level notes used to point at a placeholder note 0, owned by a placeholder user 0, so that
+-- the unique constraint on (title, user_id, parent_note_id) would also cover them.
+ALTER TABLE notes
+  ALTER COLUMN parent_note_id DROP DEFAULT,
+  ALTER COLUMN parent_note_id DROP NOT NULL;
+
+-- Must happen before note 0 goes, or deleting it would cascade to every top level note.
+UPDATE notes SET parent_note_id = NULL WHERE parent_note_id = 0;
+
+DELETE FROM notes WHERE id = 0;
+DELETE FROM note_tombstones WHERE user_id = 0;
+DELETE FROM users WHERE id = 0;
+
+-- NULLs never collide in a unique constraint, so top level notes get an index of their own.
+-- Notes in the trash give their titles up.
+ALTER TABLE notes
+  DROP CONSTRAINT notes_title_user_id_parent_note_id;
+
+CREATE UNIQUE INDEX notes_title_user_id_parent_note_id ON notes(title, user_id, parent_note_id)
+  WHERE parent_note_id IS NOT NULL AND deleted_at IS NULL;
+CREATE UNIQUE INDEX notes_title_user_id_top_level ON notes(title, user_id)
+  WHERE parent_note_id IS NULL AND deleted_at IS NULL;
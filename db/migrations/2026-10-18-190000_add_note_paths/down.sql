@@ -0,0 +1,3 @@
+DROP TRIGGER note_paths_update ON notes;
+DROP FUNCTION note_paths_update();
+DROP TABLE note_paths;
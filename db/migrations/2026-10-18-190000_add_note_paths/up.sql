@@ -0,0 +1,49 @@
+CREATE EXTENSION IF NOT EXISTS ltree;
+
+-- The ids from the top level note down to this one, e.g. `12.40.41`. Kept in its own table so
+-- that rewriting the paths of a moved subtree doesn't touch the notes themselves.
+CREATE TABLE note_paths (
+  note_id int PRIMARY KEY REFERENCES notes(id) ON DELETE CASCADE,
+  path LTREE NOT NULL
+);
+
+CREATE INDEX note_paths_path_idx ON note_paths USING GIST (path);
+
+CREATE OR REPLACE FUNCTION note_paths_update() RETURNS trigger AS $$
+DECLARE
+    new_path LTREE;
+    old_path LTREE;
+BEGIN
+    SELECT path || NEW.id::text INTO new_path FROM note_paths WHERE note_id = NEW.parent_note_id;
+    new_path := coalesce(new_path, NEW.id::text::ltree);
+
+    IF (TG_OP = 'UPDATE') THEN
+        SELECT path INTO old_path FROM note_paths WHERE note_id = NEW.id;
+    END IF;
+
+    IF (old_path IS NULL) THEN
+        INSERT INTO note_paths(note_id, path) VALUES (NEW.id, new_path);
+    ELSIF (NEW.parent_note_id IS DISTINCT FROM OLD.parent_note_id) THEN
+        UPDATE note_paths
+        SET path = CASE
+            WHEN path = old_path THEN new_path
+            ELSE new_path || subpath(path, nlevel(old_path))
+        END
+        WHERE path <@ old_path;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER note_paths_update AFTER INSERT OR UPDATE OF parent_note_id ON notes
+FOR EACH ROW EXECUTE PROCEDURE note_paths_update();
+
+-- Notes caught in a parent cycle are unreachable from the top level and get no path until moved.
+INSERT INTO note_paths(note_id, path)
+WITH RECURSIVE tree AS (
+    SELECT id, id::text::ltree AS path FROM notes WHERE parent_note_id IS NULL
+    UNION ALL
+    SELECT notes.id, tree.path || notes.id::text FROM notes
+    INNER JOIN tree ON notes.parent_note_id = tree.id
+)
+SELECT id, path FROM tree;
@@ -0,0 +1,47 @@
+CREATE TABLE note_links (
+  id SERIAL PRIMARY KEY,
+  source_note_id int NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+  -- NULL when nothing has the linked title (yet).
+  target_note_id int REFERENCES notes(id) ON DELETE SET NULL,
+  -- The text inside `[[ ]]`, or the title that was mentioned.
+  target_title TEXT NOT NULL,
+  -- `[[Title]]` links are explicit, plain mentions of a title are not.
+  explicit BOOLEAN NOT NULL
+);
+
+CREATE INDEX note_links_source_note_id_idx ON note_links(source_note_id);
+CREATE INDEX note_links_target_note_id_idx ON note_links(target_note_id);
+
+-- Index the notes that already exist. From here on the application parses each note as it is
+-- saved, using the same rules as these patterns.
+INSERT INTO note_links (source_note_id, target_note_id, target_title, explicit)
+SELECT DISTINCT ON (links.source_note_id, lower(links.target_title), targets.id)
+       links.source_note_id, targets.id, links.target_title, true
+FROM (
+    SELECT notes.id AS source_note_id, notes.user_id, notes.title AS source_title,
+           trim((regexp_matches(notes.body, '\[\[([^]|]*)[^]]*\]\]', 'g'))[1]) AS target_title
+    FROM notes
+) links
+LEFT JOIN notes targets
+  ON targets.user_id = links.user_id
+ AND targets.id <> links.source_note_id
+ AND lower(targets.title) = lower(links.target_title)
+WHERE links.target_title <> ''
+  AND lower(links.target_title) <> lower(links.source_title);
+
+INSERT INTO note_links (source_note_id, target_note_id, target_title, explicit)
+SELECT sources.id, targets.id, targets.title, false
+FROM notes sources
+INNER JOIN notes targets
+  ON targets.user_id = sources.user_id
+ AND targets.id <> sources.id
+WHERE trim(targets.title) <> ''
+  AND sources.body ~* (
+      '(^|[^[:alnum:]_])' ||
+      regexp_replace(targets.title, '([^[:alnum:][:space:]_])', '\\\1', 'g') ||
+      '([^[:alnum:]_]|$)'
+  )
+  AND NOT EXISTS (
+      SELECT 1 FROM note_links
+      WHERE note_links.source_note_id = sources.id AND note_links.target_note_id = targets.id
+  );
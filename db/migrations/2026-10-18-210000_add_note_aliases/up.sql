@@ -0,0 +1,9 @@
+CREATE TABLE note_aliases (
+  id SERIAL PRIMARY KEY,
+  note_id int NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+  alias TEXT NOT NULL
+);
+
+-- Aliases match case-insensitively, so a note can't have two that only differ in case.
+CREATE UNIQUE INDEX note_aliases_note_id_alias ON note_aliases(note_id, lower(alias));
+CREATE INDEX note_aliases_lower_alias_idx ON note_aliases(lower(alias));
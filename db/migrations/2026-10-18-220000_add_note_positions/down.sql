@@ -0,0 +1,5 @@
+DROP TRIGGER notes_reset_position ON notes;
+DROP FUNCTION notes_reset_position();
+
+ALTER TABLE notes
+  DROP COLUMN position;
@@ -0,0 +1,17 @@
+-- Where a note sits among its siblings. Notes that were never reordered are left NULL, which
+-- sorts them after the ordered ones.
+ALTER TABLE notes
+  ADD COLUMN position INT;
+
+-- A position only means something next to the old siblings, so a moved note starts unordered.
+CREATE OR REPLACE FUNCTION notes_reset_position() RETURNS trigger AS $$
+BEGIN
+    IF (NEW.parent_note_id IS DISTINCT FROM OLD.parent_note_id) THEN
+        NEW.position := NULL;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notes_reset_position BEFORE UPDATE OF parent_note_id ON notes
+FOR EACH ROW EXECUTE PROCEDURE notes_reset_position();
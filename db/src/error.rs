@@ -6,6 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::models::NoteWithTags;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -16,6 +17,24 @@ pub enum DbError {
     #[error("User is not logged in")]
     NotLoggedIn,
 
+    #[error("Not allowed to use that note")]
+    Forbidden,
+
+    #[error("{0}")]
+    BadRequest(&'static str),
+
+    #[error("Note has been changed since it was loaded")]
+    VersionMismatch(Box<NoteWithTags>),
+
+    #[error("Another note with that title already exists there")]
+    TitleConflict(Box<NoteWithTags>),
+
+    #[error("The notes have changed since the delete preview")]
+    StalePreview,
+
+    #[error("No note titled {1:?} at segment {0} of the path")]
+    PathNotFound(usize, String),
+
     #[error("Connection to Database Pool Failed")]
     R2D2(#[from] r2d2::Error),
 
@@ -53,8 +72,12 @@ impl DbError {
         use {diesel::result::DatabaseErrorKind::*, hyper::StatusCode, DbError::*};
 
         match *self {
-            NotFound => StatusCode::NOT_FOUND,
+            NotFound | PathNotFound(..) => StatusCode::NOT_FOUND,
             NotLoggedIn => StatusCode::UNAUTHORIZED,
+            Forbidden => StatusCode::FORBIDDEN,
+            BadRequest(_) => StatusCode::BAD_REQUEST,
+            VersionMismatch(_) | StalePreview => StatusCode::PRECONDITION_FAILED,
+            TitleConflict(_) => StatusCode::CONFLICT,
             UnknownDiesel(_) => StatusCode::SERVICE_UNAVAILABLE,
             DatabaseError(kind, _) => match kind {
                 UniqueViolation | ForeignKeyViolation => StatusCode::BAD_REQUEST,
@@ -72,6 +95,11 @@ mod test {
     #[test]
     fn test_code() {
         assert_eq!(DbError::NotFound.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            DbError::PathNotFound(1, "Waterdeep".to_owned()).status_code(),
+            StatusCode::NOT_FOUND
+        );
         assert_eq!(DbError::NotLoggedIn.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(DbError::Forbidden.status_code(), StatusCode::FORBIDDEN);
     }
 }
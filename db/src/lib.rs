@@ -12,7 +12,9 @@ extern crate diesel;
 pub mod error;
 pub mod models;
 #[rustfmt::skip]
+#[allow(unused_imports)]
 pub mod schema;
+pub mod sql_types;
 
 use diesel::{
     pg::PgConnection,
@@ -73,7 +75,11 @@ impl DbConnection {
 
 #[cfg(test)]
 mod testing {
-    use crate::{error::DbError, DbConnection};
+    use crate::{
+        error::DbError,
+        models::{NewNotePayload, User},
+        DbConnection,
+    };
     use diesel::{
         r2d2::{ConnectionManager, PooledConnection},
         PgConnection,
@@ -86,4 +92,51 @@ mod testing {
     pub(crate) fn db() -> Result<PooledConnection<ConnectionManager<PgConnection>>, DbError> {
         DB_CONNECTION.db()
     }
+
+    pub(crate) fn parse<'a, D: serde::Deserialize<'a>>(s: &'a str) -> D {
+        serde_json::from_str(s).unwrap()
+    }
+
+    pub(crate) fn test_user(db: &PooledConnection<ConnectionManager<PgConnection>>) -> User {
+        User::sign_up(
+            parse(
+                r#"{
+                    "email": "test@example.com",
+                    "name": "Test User",
+                    "password": "password"
+                }"#,
+            ),
+            db,
+        )
+        .unwrap()
+    }
+
+    /// A second account, for checking that one user can't reach another's data.
+    pub(crate) fn other_user(db: &PooledConnection<ConnectionManager<PgConnection>>) -> User {
+        User::sign_up(
+            parse(r#"{ "email": "other@example.com", "name": "Other", "password": "pw" }"#),
+            db,
+        )
+        .unwrap()
+    }
+
+    /// Creates a note and returns its id.
+    pub(crate) fn new_note(
+        user: &User,
+        title: &str,
+        body: &str,
+        parent_note_id: Option<i32>,
+        db: &PooledConnection<ConnectionManager<PgConnection>>,
+    ) -> i32 {
+        user.new_note(
+            &NewNotePayload {
+                title: title.to_owned(),
+                body: body.to_owned(),
+                parent_note_id,
+            },
+            db,
+        )
+        .unwrap()
+        .id
+    }
 }
@@ -8,19 +8,53 @@
 
 use crate::{
     error::{DbError, Result},
-    schema::{note_tags_id, notes, tags, users},
+    schema::{note_aliases, note_tags_id, notes, tags, users},
 };
 use diesel::{
+    dsl::{Eq, Filter, IsNull},
     pg::PgConnection,
     r2d2::{ConnectionManager, PooledConnection},
-    BelongingToDsl, Connection, ExpressionMethods, GroupedBy, QueryDsl, RunQueryDsl,
+    Connection, ExpressionMethods, GroupedBy, QueryDsl, RunQueryDsl,
 };
 use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-#[derive(Identifiable, Queryable, Deserialize, Serialize, Associations, Debug)]
+mod aliases;
+mod archive;
+mod broken_links;
+mod duplicate;
+mod links;
+mod listing;
+mod lookup;
+mod paths;
+mod policy;
+mod revisions;
+mod search;
+mod sync;
+mod tagging;
+mod transclusion;
+mod trash;
+mod tree;
+
+pub use aliases::{AliasPayload, NoteAlias};
+pub use archive::{ArchiveSubtreePayload, SubtreeArchive};
+pub use broken_links::{BrokenLink, BrokenLinkReason};
+pub use duplicate::DuplicateNotePayload;
+pub use links::{NoteLink, NoteRename, RenameNotePayload};
+pub use listing::{NoteListQuery, NotePage, NoteSort};
+pub use lookup::TitleLookup;
+pub use revisions::{DiffLine, DiffOp, NoteRevision, RevisionDiff};
+pub use search::SearchResult;
+pub use sync::{NoteChanges, NoteTombstone};
+pub use tagging::{MergeTagsPayload, RenameTagPathPayload, RenameTagPayload, TagNode, TagSummary};
+pub use transclusion::{EmbedProblem, ExpandedNote, UnresolvedEmbed, MAX_EMBED_DEPTH};
+pub use trash::{DeletePreview, InboundLink, NoteTagLink};
+pub use tree::{MoveNotePayload, NoteTree};
+
+#[derive(Identifiable, Queryable, QueryableByName, Deserialize, Serialize, Associations, Debug)]
 #[belongs_to(User)]
+#[table_name = "notes"]
 pub struct Note {
     pub id: i32,
     pub title: String,
@@ -28,29 +62,68 @@ pub struct Note {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub user_id: i32,
-    pub parent_note_id: i32,
+    pub parent_note_id: Option<i32>,
     pub archived: bool,
     pub pinned: bool,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub version: i32,
+    pub position: Option<i32>,
 }
 
 type Conn = PooledConnection<ConnectionManager<PgConnection>>;
 impl Note {
-    fn with(self, t: Vec<NoteToTag>) -> NoteWithTags {
+    fn with(self, t: Vec<NoteToTag>, a: Vec<NoteAlias>) -> NoteWithTags {
         NoteWithTags {
             id: self.id,
             title: self.title,
             body: self.body,
             tags: t.into_iter().map(|i| i.tag).collect(),
+            aliases: a.into_iter().map(|i| i.alias).collect(),
             created_at: self.created_at,
             updated_at: self.updated_at,
             user_id: self.user_id,
             parent_note_id: self.parent_note_id,
             archived: self.archived,
             pinned: self.pinned,
+            deleted_at: self.deleted_at,
+            version: self.version,
+            position: self.position,
         }
     }
 }
 
+fn load_tags(all_notes: Vec<Note>, db: &Conn) -> Result<Vec<NoteWithTags>> {
+    let tags_query = {
+        use crate::schema::note_tags_id::dsl::*;
+        note_tags_id
+            .filter(note_id.eq_any(all_notes.iter().map(|n| n.id).collect::<Vec<_>>()))
+            .inner_join(tags::table)
+            .select((id, note_id, tag_id, tags::tag))
+    };
+
+    let note_tags = tags_query.load::<NoteToTag>(db)?.grouped_by(&all_notes);
+    let aliases = note_aliases::table
+        .filter(note_aliases::note_id.eq_any(all_notes.iter().map(|n| n.id).collect::<Vec<_>>()))
+        .order((note_aliases::alias, note_aliases::id))
+        .load::<NoteAlias>(db)?
+        .grouped_by(&all_notes);
+
+    Ok(all_notes
+        .into_iter()
+        .zip(note_tags)
+        .zip(aliases)
+        .map(|((n, ts), a)| n.with(ts, a))
+        .collect::<Vec<_>>())
+}
+
+/// Escapes `%`, `_` and `\` so that `pattern` only matches itself in a `LIKE`.
+fn escape_like(pattern: &str) -> String {
+    pattern
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 pub trait WithTags {
     type Output;
     fn with_tags(
@@ -73,6 +146,12 @@ impl WithTags for Note {
             .load::<String>(c)
             .ok()?;
         tags.sort();
+        let aliases = note_aliases::table
+            .filter(note_aliases::note_id.eq(self.id))
+            .order((note_aliases::alias, note_aliases::id))
+            .select(note_aliases::alias)
+            .load::<String>(c)
+            .ok()?;
 
         Some(NoteWithTags {
             id: self.id,
@@ -84,24 +163,35 @@ impl WithTags for Note {
             parent_note_id: self.parent_note_id,
             archived: self.archived,
             pinned: self.pinned,
+            deleted_at: self.deleted_at,
+            version: self.version,
+            position: self.position,
             tags,
+            aliases,
         })
     }
 }
 
-#[derive(Serialize, Deserialize, JsonSchema)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 #[schemars(deny_unknown_fields)]
 pub struct NoteWithTags {
     pub id: i32,
     pub title: String,
     pub body: String,
     pub tags: Vec<String>,
+    /// Other names the note can be linked and looked up by, sorted.
+    pub aliases: Vec<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub user_id: i32,
-    pub parent_note_id: i32,
+    pub parent_note_id: Option<i32>,
     pub archived: bool,
     pub pinned: bool,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub version: i32,
+    /// Where the note sits among its siblings, once they have been reordered. Notes without one
+    /// come after the rest.
+    pub position: Option<i32>,
 }
 
 #[derive(Identifiable, Queryable, Serialize, Associations)]
@@ -115,11 +205,13 @@ pub struct NoteToTag {
 }
 
 #[derive(Identifiable, Queryable, Serialize, Associations)]
+#[belongs_to(User)]
 pub struct Tag {
     pub id: i32,
     pub tag: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub user_id: i32,
 }
 
 #[derive(Identifiable, Queryable, Associations)]
@@ -141,13 +233,28 @@ pub struct NewNotePayload {
     pub parent_note_id: Option<i32>,
 }
 
+/// Tells a missing field (`None`) apart from an explicit `null` (`Some(None)`).
+fn nullable<'de, D, T>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
 #[derive(AsChangeset, Deserialize, Serialize, Default, JsonSchema)]
 #[schemars(deny_unknown_fields)]
 #[table_name = "notes"]
 pub struct UpdateNotePayload {
     pub title: Option<String>,
     pub body: Option<String>,
-    pub parent_note_id: Option<i32>,
+    /// `null` moves the note to the top level.
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_note_id: Option<Option<i32>>,
     pub archived: Option<bool>,
     pub pinned: Option<bool>,
 }
@@ -205,42 +312,38 @@ pub struct User {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+type LiveNotes = Filter<Filter<notes::table, Eq<notes::user_id, i32>>, IsNull<notes::deleted_at>>;
+
 impl User {
+    /// The user's notes, leaving out anything in the trash.
+    fn live_notes(&self) -> LiveNotes {
+        notes::table
+            .filter(notes::user_id.eq(self.id))
+            .filter(notes::deleted_at.is_null())
+    }
+
     pub fn new_note(&self, new_note: &NewNotePayload, db: &Conn) -> Result<NoteWithTags> {
         use crate::schema::notes::dsl::*;
 
-        diesel::insert_into(notes)
-            .values((new_note, user_id.eq(self.id)))
-            .get_result::<Note>(db)?
-            .with_tags(db)
-            .ok_or(DbError::NotFound)
+        db.transaction(|| {
+            self.authorize_parent(new_note.parent_note_id, db)?;
+            self.authorize_title(None, &new_note.title, new_note.parent_note_id, db)?;
+            let note = diesel::insert_into(notes)
+                .values((new_note, user_id.eq(self.id)))
+                .get_result::<Note>(db)?;
+            self.index_links_from(&note, db)?;
+            self.index_links_to(&note, db)?;
+
+            note.with_tags(db).ok_or(DbError::NotFound)
+        })
     }
 
     pub fn list_notes(&self, db: &Conn) -> Result<Vec<NoteWithTags>> {
-        let all_notes = {
-            use crate::schema::notes::dsl::*;
-            notes.filter(user_id.eq(self.id)).load::<Note>(db)?
-        };
-
-        let tags_query = {
-            use crate::schema::note_tags_id::dsl::*;
-            note_tags_id
-                .filter(note_id.eq_any(all_notes.iter().map(|n| n.id).collect::<Vec<_>>()))
-                .inner_join(tags::table)
-                .select((id, note_id, tag_id, tags::tag))
-        };
-
-        let note_tags = tags_query.load::<NoteToTag>(db)?.grouped_by(&all_notes);
-
-        Ok(all_notes
-            .into_iter()
-            .zip(note_tags)
-            .map(|(n, ts)| n.with(ts))
-            .collect::<Vec<_>>())
+        load_tags(self.live_notes().load::<Note>(db)?, db)
     }
 
     pub fn note(&self, id: i32, db: &Conn) -> Result<NoteWithTags> {
-        Note::belonging_to(self)
+        self.live_notes()
             .find(id)
             .first::<Note>(db)?
             .with_tags(db)
@@ -253,18 +356,60 @@ impl User {
         note: &UpdateNotePayload,
         db: &Conn,
     ) -> Result<NoteWithTags> {
-        diesel::update(Note::belonging_to(self).find(id))
-            .set(note)
-            .execute(db)?;
+        db.transaction(|| {
+            let current = self.authorize_note(id, db)?;
+            if let Some(parent_note_id) = note.parent_note_id {
+                self.authorize_move(id, parent_note_id, db)?;
+            }
+            if note.title.is_some() || note.parent_note_id.is_some() {
+                self.authorize_title(
+                    Some(id),
+                    note.title.as_deref().unwrap_or(&current.title),
+                    note.parent_note_id.unwrap_or(current.parent_note_id),
+                    db,
+                )?;
+            }
+            let updated = diesel::update(self.live_notes().find(id))
+                .set(note)
+                .get_result::<Note>(db)?;
+            if note.title.is_some() || note.body.is_some() {
+                self.index_links_from(&updated, db)?;
+            }
+            if note.title.is_some() {
+                self.index_links_to(&updated, db)?;
+            }
+
+            self.note(id, db)
+        })
+    }
+
+    /// Locks the note for the rest of the transaction, failing with `DbError::VersionMismatch` if
+    /// it has been changed since `version`.
+    pub fn check_note_version(&self, id: i32, version: i32, db: &Conn) -> Result<()> {
+        let current = self.authorize_note(id, db)?;
 
-        self.note(id, db)
+        if current.version == version {
+            Ok(())
+        } else {
+            Err(DbError::VersionMismatch(Box::new(
+                current.with_tags(db).ok_or(DbError::NotFound)?,
+            )))
+        }
     }
 
-    pub fn delete_note(&self, id: i32, db: &Conn) -> bool {
-        diesel::delete(Note::belonging_to(self).find(id))
-            .execute(db)
-            .unwrap_or(0)
-            != 0
+    /// Bumps `updated_at` and `version` on notes whose tags or aliases changed. Those live in
+    /// their own tables, so otherwise syncing and editing clients would never notice.
+    fn touch_notes(&self, ids: &[i32], db: &Conn) -> Result<Vec<Note>> {
+        Ok(diesel::update(
+            notes::table
+                .filter(notes::user_id.eq(self.id))
+                .filter(notes::id.eq_any(ids)),
+        )
+        .set((
+            notes::updated_at.eq(diesel::dsl::now),
+            notes::version.eq(notes::version + 1),
+        ))
+        .get_results::<Note>(db)?)
     }
 
     pub fn set_note_tags(
@@ -273,9 +418,16 @@ impl User {
         set_tags: &[String],
         db: &Conn,
     ) -> Result<NoteWithTags> {
-        db.transaction::<(), diesel::result::Error, _>(|| {
+        db.transaction::<(), DbError, _>(|| {
             use crate::schema::{note_tags_id::dsl::*, tags::dsl::*};
 
+            self.authorize_note(current_note_id, db)?;
+
+            let set_tags = set_tags
+                .iter()
+                .filter_map(|t| tagging::normalize_tag(t))
+                .collect::<Vec<_>>();
+
             // TODO: Server needs Postgres 9.5 to support ON CONFLICT DO NOTHING
             // BODY: In the meantime, we need to calculate exactly which tags need to be added.
             let tag_set = set_tags
@@ -284,7 +436,8 @@ impl User {
                 .collect::<HashSet<_>>();
 
             let known_tags = tags
-                .filter(&tag.eq_any(set_tags))
+                .filter(user_id.eq(self.id))
+                .filter(tag.eq_any(&set_tags))
                 .select(tag)
                 .get_results::<String>(db)?
                 .into_iter()
@@ -293,13 +446,23 @@ impl User {
             let new_tags = tag_set.difference(&known_tags).collect::<Vec<_>>();
 
             diesel::insert_into(tags)
-                .values(&new_tags.iter().map(|t| tag.eq(t)).collect::<Vec<_>>())
+                .values(
+                    &new_tags
+                        .iter()
+                        .map(|t| (tag.eq(t), user_id.eq(self.id)))
+                        .collect::<Vec<_>>(),
+                )
                 .execute(db)?;
 
-            let all_tags = tags.filter(tag.eq_any(set_tags)).load::<Tag>(db)?;
+            let all_tags = tags
+                .filter(user_id.eq(self.id))
+                .filter(tag.eq_any(&set_tags))
+                .load::<Tag>(db)?;
 
             diesel::delete(note_tags_id.filter(note_id.eq(current_note_id))).execute(db)?;
 
+            self.touch_notes(&[current_note_id], db)?;
+
             diesel::insert_into(note_tags_id)
                 .values(
                     all_tags
@@ -309,10 +472,11 @@ impl User {
                 )
                 .execute(db)?;
 
-            let tag_ids = note_tags_id.select(tag_id).get_results::<i32>(db)?;
-
-            diesel::delete(tags.filter(crate::schema::tags::dsl::id.ne_all(tag_ids)))
-                .execute(db)?;
+            // Only this user's tags are collected, so other accounts never contend on them.
+            diesel::delete(tags.filter(user_id.eq(self.id)).filter(diesel::dsl::not(
+                crate::schema::tags::dsl::id.eq_any(note_tags_id.select(tag_id)),
+            )))
+            .execute(db)?;
 
             Ok(())
         })?;
@@ -344,27 +508,9 @@ impl User {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::testing::db;
+    use crate::testing::{db, other_user, parse, test_user};
     use diesel::Connection;
 
-    fn parse<'a, D: serde::Deserialize<'a>>(s: &'a str) -> D {
-        serde_json::from_str(s).unwrap()
-    }
-
-    fn test_user(db: &Conn) -> User {
-        User::sign_up(
-            parse(
-                r#"{
-                    "email": "test@example.com",
-                    "name": "Test User",
-                    "password": "password"
-                }"#,
-            ),
-            db,
-        )
-        .unwrap()
-    }
-
     #[test]
     fn test_with_tags() {
         let db = db().unwrap();
@@ -380,7 +526,7 @@ mod test {
                 .update_note(note.id, &parse(r#"{ "title": "New Title" }"#), &db)
                 .unwrap();
             assert_eq!(note.title, "New Title");
-            user.delete_note(note.id, &db);
+            user.delete_note(note.id, &db).unwrap();
             assert_eq!(user.list_notes(&db).unwrap().len(), 0);
             Ok(())
         });
@@ -418,6 +564,80 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_tags_are_per_user() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let other = other_user(&db);
+
+            let mine = user
+                .new_note(&parse(r#"{ "title": "Mine", "body": "" }"#), &db)
+                .unwrap();
+            let theirs = other
+                .new_note(&parse(r#"{ "title": "Theirs", "body": "" }"#), &db)
+                .unwrap();
+            user.set_note_tags(mine.id, &["npc".to_owned()], &db)
+                .unwrap();
+            other
+                .set_note_tags(theirs.id, &["npc".to_owned()], &db)
+                .unwrap();
+
+            let owners = tags::table
+                .filter(tags::tag.eq("npc"))
+                .select(tags::user_id)
+                .load::<i32>(&db)?;
+            assert_eq!(owners.len(), 2);
+
+            // Dropping the tag from one account leaves the other's alone.
+            user.set_note_tags(mine.id, &[], &db).unwrap();
+            let owners = tags::table
+                .filter(tags::tag.eq("npc"))
+                .select(tags::user_id)
+                .load::<i32>(&db)?;
+            assert_eq!(owners, vec![other.id]);
+            assert_eq!(
+                other.note(theirs.id, &db).unwrap().tags,
+                vec!["npc".to_owned()]
+            );
+
+            // Tagged notes can still be purged.
+            other.delete_note(theirs.id, &db).unwrap();
+            other.purge_note(theirs.id, &db).unwrap();
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_note_version() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let note = user
+                .new_note(&parse(r#"{ "title": "Title", "body": "Body" }"#), &db)
+                .unwrap();
+            assert_eq!(note.version, 1);
+
+            let updated = user
+                .update_note(note.id, &parse(r#"{ "body": "New Body" }"#), &db)
+                .unwrap();
+            assert_eq!(updated.version, 2);
+            let tagged = user
+                .set_note_tags(note.id, &["Tag".to_owned()], &db)
+                .unwrap();
+            assert_eq!(tagged.version, 3);
+
+            user.check_note_version(note.id, 3, &db).unwrap();
+            match user.check_note_version(note.id, 1, &db) {
+                Err(DbError::VersionMismatch(current)) => assert_eq!(current.body, "New Body"),
+                _ => panic!("expected a version mismatch"),
+            }
+
+            Ok(())
+        });
+    }
+
     #[test]
     fn test_creating_user() {
         let db = db().unwrap();
@@ -0,0 +1,215 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Other names for a note, e.g. "the Baron" for "Baron von Strahd". Links and lookups match them
+//! the same way they match titles.
+
+use super::{Conn, Note, User};
+use crate::{
+    error::{DbError, Result},
+    schema::{note_aliases, notes},
+};
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Identifiable, Queryable, Associations, Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(deny_unknown_fields)]
+#[belongs_to(Note)]
+#[table_name = "note_aliases"]
+pub struct NoteAlias {
+    pub id: i32,
+    pub note_id: i32,
+    pub alias: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct AliasPayload {
+    /// Surrounding whitespace is dropped.
+    pub alias: String,
+}
+
+impl AliasPayload {
+    fn alias(&self) -> Result<&str> {
+        match self.alias.trim() {
+            "" => Err(DbError::BadRequest("An alias can't be empty")),
+            alias => Ok(alias),
+        }
+    }
+}
+
+impl User {
+    /// The note's aliases, sorted.
+    pub fn note_aliases(&self, note_id: i32, db: &Conn) -> Result<Vec<NoteAlias>> {
+        self.live_notes()
+            .find(note_id)
+            .select(notes::id)
+            .first::<i32>(db)?;
+        Ok(note_aliases::table
+            .filter(note_aliases::note_id.eq(note_id))
+            .order((note_aliases::alias, note_aliases::id))
+            .load(db)?)
+    }
+
+    pub fn add_alias(&self, note_id: i32, alias: &AliasPayload, db: &Conn) -> Result<NoteAlias> {
+        db.transaction(|| {
+            self.authorize_note(note_id, db)?;
+            let added = diesel::insert_into(note_aliases::table)
+                .values((
+                    note_aliases::note_id.eq(note_id),
+                    note_aliases::alias.eq(alias.alias()?),
+                ))
+                .get_result::<NoteAlias>(db)?;
+            self.aliases_changed(note_id, db)?;
+
+            Ok(added)
+        })
+    }
+
+    pub fn update_alias(
+        &self,
+        note_id: i32,
+        alias_id: i32,
+        alias: &AliasPayload,
+        db: &Conn,
+    ) -> Result<NoteAlias> {
+        db.transaction(|| {
+            self.authorize_note(note_id, db)?;
+            let updated = diesel::update(
+                note_aliases::table
+                    .find(alias_id)
+                    .filter(note_aliases::note_id.eq(note_id)),
+            )
+            .set(note_aliases::alias.eq(alias.alias()?))
+            .get_result::<NoteAlias>(db)?;
+            self.aliases_changed(note_id, db)?;
+
+            Ok(updated)
+        })
+    }
+
+    pub fn delete_alias(&self, note_id: i32, alias_id: i32, db: &Conn) -> Result<()> {
+        db.transaction(|| {
+            self.authorize_note(note_id, db)?;
+            let deleted = diesel::delete(
+                note_aliases::table
+                    .find(alias_id)
+                    .filter(note_aliases::note_id.eq(note_id)),
+            )
+            .execute(db)?;
+            if deleted == 0 {
+                return Err(DbError::NotFound);
+            }
+
+            self.aliases_changed(note_id, db)
+        })
+    }
+
+    /// Bumps the note, then re-indexes the notes that may link to it by name.
+    fn aliases_changed(&self, note_id: i32, db: &Conn) -> Result<()> {
+        let note = self
+            .touch_notes(&[note_id], db)?
+            .pop()
+            .ok_or(DbError::NotFound)?;
+
+        self.index_links_to(&note, db)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::TitleLookup;
+    use crate::testing::{db, new_note, test_user};
+
+    fn alias(alias: &str) -> AliasPayload {
+        AliasPayload {
+            alias: alias.to_owned(),
+        }
+    }
+
+    fn linked(user: &User, id: i32, db: &Conn) -> Vec<String> {
+        user.links(id, db)
+            .unwrap()
+            .into_iter()
+            .map(|l| l.note.title)
+            .collect()
+    }
+
+    #[test]
+    fn test_note_aliases() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let strahd = new_note(&user, "Baron von Strahd", "", None, &db);
+            let journal = new_note(&user, "Journal", "Met [[the Baron]] at dinner.", None, &db);
+            assert!(linked(&user, journal, &db).is_empty());
+
+            let baron = user.add_alias(strahd, &alias(" the Baron "), &db).unwrap();
+            assert_eq!(baron.alias, "the Baron");
+            assert!(matches!(
+                user.add_alias(strahd, &alias("THE BARON"), &db),
+                Err(DbError::DatabaseError(..))
+            ));
+            assert!(matches!(
+                user.add_alias(strahd, &alias("  "), &db),
+                Err(DbError::BadRequest(_))
+            ));
+            user.add_alias(strahd, &alias("Devil Strahd"), &db).unwrap();
+
+            let note = user.note(strahd, &db).unwrap();
+            assert_eq!(note.aliases, vec!["Devil Strahd", "the Baron"]);
+            assert_eq!(note.version, 3);
+            assert_eq!(linked(&user, journal, &db), vec!["Baron von Strahd"]);
+
+            // Aliases are looked up like titles, and shared ones need disambiguating.
+            match user.lookup_title("THE BARON", &db).unwrap() {
+                TitleLookup::Note { note } => assert_eq!(note.id, strahd),
+                _ => panic!("expected a single note"),
+            }
+            let other = new_note(&user, "The Baron", "", None, &db);
+            match user.lookup_title("the baron", &db).unwrap() {
+                TitleLookup::Disambiguation { notes } => assert_eq!(
+                    notes.iter().map(|n| n.id).collect::<Vec<_>>(),
+                    vec![strahd, other]
+                ),
+                _ => panic!("expected a disambiguation"),
+            }
+            assert!(matches!(
+                user.lookup_title("Ireena", &db),
+                Err(DbError::NotFound)
+            ));
+            user.delete_note(other, &db).unwrap();
+
+            user.update_alias(strahd, baron.id, &alias("the Count"), &db)
+                .unwrap();
+            assert!(linked(&user, journal, &db).is_empty());
+            assert!(matches!(
+                user.update_alias(journal, baron.id, &alias("the Baron"), &db),
+                Err(DbError::NotFound)
+            ));
+
+            user.delete_alias(strahd, baron.id, &db).unwrap();
+            assert!(matches!(
+                user.delete_alias(strahd, baron.id, &db),
+                Err(DbError::NotFound)
+            ));
+            assert_eq!(
+                user.note_aliases(strahd, &db)
+                    .unwrap()
+                    .into_iter()
+                    .map(|a| a.alias)
+                    .collect::<Vec<_>>(),
+                vec!["Devil Strahd"]
+            );
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{Conn, NoteWithTags, User};
+use crate::{error::Result, schema::notes};
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+/// Matches notes with an archived note somewhere above them.
+pub(super) const UNDER_ARCHIVED: &str = r#"
+    EXISTS (
+        SELECT 1 FROM note_paths own
+        INNER JOIN note_paths above
+            ON above.path @> own.path AND above.note_id <> own.note_id
+        INNER JOIN notes ancestor ON ancestor.id = above.note_id
+        WHERE own.note_id = notes.id
+          AND ancestor.archived
+          AND ancestor.deleted_at IS NULL
+    )
+"#;
+
+#[derive(Deserialize, Serialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ArchiveSubtreePayload {
+    /// Only count what would change, without saving anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct SubtreeArchive {
+    pub note: NoteWithTags,
+    /// How many notes in the subtree, this one included, changed or would change.
+    pub changed: i64,
+}
+
+impl User {
+    /// Archives or unarchives a note and everything under it.
+    pub fn archive_subtree(
+        &self,
+        id: i32,
+        archived: bool,
+        archive: &ArchiveSubtreePayload,
+        db: &Conn,
+    ) -> Result<SubtreeArchive> {
+        db.transaction(|| {
+            self.authorize_note(id, db)?;
+            let changing = std::iter::once(self.note(id, db)?)
+                .chain(self.descendants(id, db)?)
+                .filter(|n| n.archived != archived)
+                .map(|n| n.id)
+                .collect::<Vec<_>>();
+
+            if !archive.dry_run {
+                diesel::update(self.live_notes().filter(notes::id.eq_any(&changing)))
+                    .set((
+                        notes::archived.eq(archived),
+                        notes::updated_at.eq(diesel::dsl::now),
+                        notes::version.eq(notes::version + 1),
+                    ))
+                    .execute(db)?;
+            }
+
+            Ok(SubtreeArchive {
+                note: self.note(id, db)?,
+                changed: changing.len() as i64,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::NoteListQuery;
+    use crate::testing::{db, new_note, test_user};
+
+    fn listed(user: &User, under_archived: Option<bool>, db: &Conn) -> Vec<String> {
+        let mut titles = user
+            .list_notes_page(
+                &NoteListQuery {
+                    under_archived,
+                    ..NoteListQuery::default()
+                },
+                db,
+            )
+            .unwrap()
+            .notes
+            .into_iter()
+            .map(|n| n.title)
+            .collect::<Vec<_>>();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn test_archive_subtree() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let arc = new_note(&user, "Death House", "", None, &db);
+            let floor = new_note(&user, "Second Floor", "", Some(arc), &db);
+            let nursery = new_note(&user, "Nursery", "", Some(floor), &db);
+            new_note(&user, "Barovia", "", None, &db);
+
+            let preview = user
+                .archive_subtree(arc, true, &ArchiveSubtreePayload { dry_run: true }, &db)
+                .unwrap();
+            assert_eq!(preview.changed, 3);
+            assert!(!preview.note.archived);
+            assert!(!user.note(nursery, &db).unwrap().archived);
+
+            // Already archived notes are left alone.
+            user.archive_subtree(floor, true, &ArchiveSubtreePayload::default(), &db)
+                .unwrap();
+            let archived = user
+                .archive_subtree(arc, true, &ArchiveSubtreePayload::default(), &db)
+                .unwrap();
+            assert_eq!(archived.changed, 1);
+            assert!(archived.note.archived);
+            assert!(user.note(nursery, &db).unwrap().archived);
+
+            assert_eq!(
+                listed(&user, Some(false), &db),
+                vec!["Barovia", "Death House"]
+            );
+            assert_eq!(
+                listed(&user, Some(true), &db),
+                vec!["Nursery", "Second Floor"]
+            );
+
+            let unarchived = user
+                .archive_subtree(floor, false, &ArchiveSubtreePayload::default(), &db)
+                .unwrap();
+            assert_eq!(unarchived.changed, 2);
+            assert!(user.note(arc, &db).unwrap().archived);
+            assert!(!user.note(nursery, &db).unwrap().archived);
+
+            Ok(())
+        });
+    }
+}
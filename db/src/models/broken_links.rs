@@ -0,0 +1,210 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{links::explicit_links, lookup::by_name, Conn, User};
+use crate::error::Result;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokenLinkReason {
+    Missing,
+    Archived,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(deny_unknown_fields)]
+pub struct BrokenLink {
+    /// The note containing the link.
+    pub note_id: i32,
+    pub note_title: String,
+    /// The link as written, e.g. `[[Strahd]]`, `/note/12` or `/disambiguation/12,40`.
+    pub link: String,
+    /// The note the link leads to, if there is one.
+    pub target_id: Option<i32>,
+    /// `missing` if nothing has that title, alias or id, or it is in the trash. `archived` if
+    /// every note the link could lead to is archived.
+    pub reason: BrokenLinkReason,
+}
+
+/// A `/note/<id>` or `/disambiguation/<ids>` URL.
+pub(super) struct IdLink {
+    /// Where the whole URL path is.
+    pub link: Range<usize>,
+    /// Where the comma separated ids are.
+    pub ids: Range<usize>,
+    pub targets: Vec<i32>,
+}
+
+/// Every `/note/<id>` and `/disambiguation/<ids>` URL in `body`, in order.
+pub(super) fn id_links(body: &str) -> Vec<IdLink> {
+    let mut links = vec![];
+    for &(prefix, many) in &[("/note/", false), ("/disambiguation/", true)] {
+        for (start, _) in body.match_indices(prefix) {
+            let ids = start + prefix.len();
+            let end = body[ids..]
+                .find(|c: char| !(c.is_ascii_digit() || (many && c == ',')))
+                .map_or(body.len(), |len| ids + len);
+            let end = ids + body[ids..end].trim_end_matches(',').len();
+            let targets = body[ids..end]
+                .split(',')
+                .filter_map(|id| id.parse().ok())
+                .collect::<Vec<_>>();
+            if !targets.is_empty() {
+                links.push(IdLink {
+                    link: start..end,
+                    ids: ids..end,
+                    targets,
+                });
+            }
+        }
+    }
+    links.sort_by_key(|l| l.link.start);
+    links
+}
+
+impl User {
+    /// Every link in the user's notes that leads to a note that is missing, trashed or archived,
+    /// sorted by the title of the note containing it.
+    pub fn broken_links(&self, db: &Conn) -> Result<Vec<BrokenLink>> {
+        let mut notes = self.list_notes(db)?;
+        notes.sort_by(|a, b| (&a.title, a.id).cmp(&(&b.title, b.id)));
+
+        let by_id = notes.iter().map(|n| (n.id, n)).collect::<HashMap<_, _>>();
+        let by_title = by_name(&notes);
+
+        let mut broken = vec![];
+        for note in &notes {
+            let mut found = vec![];
+            for title in explicit_links(&note.body) {
+                let link = format!("[[{}]]", title);
+                match by_title.get(&title.to_lowercase()) {
+                    None => found.push((link, None, BrokenLinkReason::Missing)),
+                    Some(targets) if targets.iter().all(|t| t.archived) => {
+                        found.push((link, Some(targets[0].id), BrokenLinkReason::Archived))
+                    }
+                    Some(_) => {}
+                }
+            }
+            for IdLink { link, targets, .. } in id_links(&note.body) {
+                let link = &note.body[link];
+                for id in targets {
+                    match by_id.get(&id) {
+                        None => found.push((link.to_owned(), Some(id), BrokenLinkReason::Missing)),
+                        Some(target) if target.archived => {
+                            found.push((link.to_owned(), Some(id), BrokenLinkReason::Archived))
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+
+            let mut seen = HashSet::new();
+            for (link, target_id, reason) in found {
+                if seen.insert((link.clone(), target_id)) {
+                    broken.push(BrokenLink {
+                        note_id: note.id,
+                        note_title: note.title.clone(),
+                        link,
+                        target_id,
+                        reason,
+                    });
+                }
+            }
+        }
+        Ok(broken)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, new_note, parse, test_user};
+    use diesel::Connection;
+
+    #[test]
+    fn test_id_links() {
+        let body = "See /disambiguation/3,4, [Bob](/note/12). /note/x /notes/5";
+        assert_eq!(
+            id_links(body)
+                .into_iter()
+                .map(|l| (&body[l.link], &body[l.ids], l.targets))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/disambiguation/3,4", "3,4", vec![3, 4]),
+                ("/note/12", "12", vec![12])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_broken_links() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let map = new_note(&user, "Old Map", "", None, &db);
+            user.update_note(map, &parse(r#"{ "archived": true }"#), &db)
+                .unwrap();
+            let strahd = new_note(&user, "Strahd", "", None, &db);
+            let trashed = new_note(&user, "Ireena", "", None, &db);
+            user.delete_note(trashed, &db).unwrap();
+
+            let body = format!(
+                "[[Strahd]] [[Old Map]] [[Van Richten]] [[van richten]] \
+                 /note/{0} /disambiguation/{0},{1},{2} /note/{1}",
+                strahd, map, trashed
+            );
+            let journal = new_note(&user, "Journal", &body, None, &db);
+            new_note(&user, "Notes", "All good: [[Strahd]]", None, &db);
+
+            let broken = user
+                .broken_links(&db)
+                .unwrap()
+                .into_iter()
+                .map(|b| {
+                    assert_eq!(b.note_id, journal);
+                    (b.link, b.target_id, b.reason)
+                })
+                .collect::<Vec<_>>();
+            let disambiguation = format!("/disambiguation/{},{},{}", strahd, map, trashed);
+            assert_eq!(
+                broken,
+                vec![
+                    (
+                        "[[Old Map]]".to_owned(),
+                        Some(map),
+                        BrokenLinkReason::Archived
+                    ),
+                    (
+                        "[[Van Richten]]".to_owned(),
+                        None,
+                        BrokenLinkReason::Missing
+                    ),
+                    (
+                        disambiguation.clone(),
+                        Some(map),
+                        BrokenLinkReason::Archived
+                    ),
+                    (disambiguation, Some(trashed), BrokenLinkReason::Missing),
+                    (
+                        format!("/note/{}", map),
+                        Some(map),
+                        BrokenLinkReason::Archived
+                    ),
+                ]
+            );
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,356 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{
+    broken_links::id_links, links::link_targets, lookup::by_name, nullable, Conn, NewNotePayload,
+    NoteWithTags, UpdateNotePayload, User,
+};
+use crate::{
+    error::{DbError, Result},
+    schema::notes,
+};
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Serialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct DuplicateNotePayload {
+    /// Copy everything under the note as well.
+    #[serde(default)]
+    pub deep: bool,
+    /// Give each copy the tags of its original.
+    #[serde(default)]
+    pub tags: bool,
+    /// Where to put the copy. Left out, it goes next to the original. `null` puts it at the top
+    /// level.
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent_note_id: Option<Option<i32>>,
+}
+
+/// How a `[[link]]` to a copied note is written to lead to the copy instead.
+enum CopyLink {
+    /// The copy's title, which no other note goes by.
+    Title(String),
+    /// The copy shares its title, so the link becomes `[label](/note/<id>)`.
+    Id(i32),
+}
+
+/// `body` with its `/note/<id>` and `/disambiguation/<ids>` URLs pointed at the copies in `copies`.
+fn rewrite_id_links(body: &str, copies: &HashMap<i32, (i32, String)>) -> String {
+    let mut rewritten = String::with_capacity(body.len());
+    let mut copied = 0;
+    for link in id_links(body) {
+        rewritten.push_str(&body[copied..link.ids.start]);
+        let targets = link
+            .targets
+            .iter()
+            .map(|id| copies.get(id).map_or(*id, |(copy, _)| *copy).to_string())
+            .collect::<Vec<_>>();
+        rewritten.push_str(&targets.join(","));
+        copied = link.ids.end;
+    }
+    rewritten.push_str(&body[copied..]);
+    rewritten
+}
+
+/// `body` with its `[[links]]` to the lowercase names in `links` pointed at the copies. Embeds
+/// can only name their target, so those that would need an id are left alone.
+fn rewrite_title_links(body: &str, links: &HashMap<String, CopyLink>) -> String {
+    let mut rewritten = String::with_capacity(body.len());
+    let mut copied = 0;
+    for target in link_targets(body) {
+        let written = body[target.clone()].trim();
+        match links.get(&written.to_lowercase()) {
+            Some(CopyLink::Title(title)) => {
+                rewritten.push_str(&body[copied..target.start]);
+                rewritten.push_str(title);
+                copied = target.end;
+            }
+            Some(CopyLink::Id(id)) if !body[..target.start - 2].ends_with('!') => {
+                // Labels can't contain `]`, so this is where the link ends.
+                let end = target.end + body[target.end..].find("]]").unwrap_or(0);
+                let label = body[target.end..end]
+                    .strip_prefix('|')
+                    .map_or(written, str::trim);
+                rewritten.push_str(&body[copied..target.start - 2]);
+                rewritten.push_str(&format!("[{}](/note/{})", label, id));
+                copied = end + 2;
+            }
+            _ => {}
+        }
+    }
+    rewritten.push_str(&body[copied..]);
+    rewritten
+}
+
+impl User {
+    /// The first of "Title", "Title (copy)", "Title (copy 2)", ... that no note under
+    /// `parent_note_id` has.
+    fn free_title(&self, title: &str, parent_note_id: Option<i32>, db: &Conn) -> Result<String> {
+        let mut candidate = title.to_owned();
+        let mut copies = 0;
+        loop {
+            match self.authorize_title(None, &candidate, parent_note_id, db) {
+                Err(DbError::TitleConflict(_)) => {}
+                result => return result.map(|_| candidate),
+            }
+            copies += 1;
+            candidate = match copies {
+                1 => format!("{} (copy)", title),
+                n => format!("{} (copy {})", title, n),
+            };
+        }
+    }
+
+    /// Copies a note, and with `deep` everything under it, returning the new top copy. The top
+    /// copy gets a title that is free where it lands. Inside the copies, links by id or by name
+    /// to any of the copied notes are pointed at the copies.
+    pub fn duplicate_note(
+        &self,
+        id: i32,
+        duplicate: &DuplicateNotePayload,
+        db: &Conn,
+    ) -> Result<NoteWithTags> {
+        db.transaction(|| {
+            let original = self.authorize_note(id, db)?;
+            let parent_note_id = duplicate.parent_note_id.unwrap_or(original.parent_note_id);
+            self.authorize_parent(parent_note_id, db)?;
+            let title = self.free_title(&original.title, parent_note_id, db)?;
+
+            let before = self.list_notes(db)?;
+            let mut originals = vec![self.note(id, db)?];
+            if duplicate.deep {
+                originals.extend(self.descendants(id, db)?);
+            }
+
+            // Descendants are sorted so that each parent is copied before its children.
+            let mut copies = HashMap::<i32, (i32, String)>::new();
+            for note in &originals {
+                let copy = self.new_note(
+                    &NewNotePayload {
+                        title: if note.id == id {
+                            title.clone()
+                        } else {
+                            note.title.clone()
+                        },
+                        body: note.body.clone(),
+                        parent_note_id: if note.id == id {
+                            parent_note_id
+                        } else {
+                            note.parent_note_id.map(|parent| copies[&parent].0)
+                        },
+                    },
+                    db,
+                )?;
+                // The top copy lands after its new siblings, the rest keep their order.
+                if note.id != id && note.position.is_some() {
+                    diesel::update(self.live_notes().find(copy.id))
+                        .set(notes::position.eq(note.position))
+                        .execute(db)?;
+                }
+                copies.insert(note.id, (copy.id, copy.title));
+            }
+
+            // Only names that led to a single copied note are rewritten.
+            let after = self.list_notes(db)?;
+            let after = by_name(&after);
+            let links = by_name(&before)
+                .into_iter()
+                .filter_map(|(name, named)| match named.as_slice() {
+                    [original] => copies
+                        .get(&original.id)
+                        .map(|(copy, title)| (name, copy, title)),
+                    _ => None,
+                })
+                .filter_map(|(name, copy, title)| {
+                    let lower = title.to_lowercase();
+                    if after[&lower].len() > 1 {
+                        Some((name, CopyLink::Id(*copy)))
+                    } else if lower != name {
+                        Some((name, CopyLink::Title(title.clone())))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<HashMap<_, _>>();
+
+            for note in &originals {
+                let body = rewrite_title_links(&rewrite_id_links(&note.body, &copies), &links);
+                self.update_note(
+                    copies[&note.id].0,
+                    &UpdateNotePayload {
+                        body: if body == note.body { None } else { Some(body) },
+                        archived: Some(note.archived),
+                        pinned: Some(note.pinned),
+                        ..UpdateNotePayload::default()
+                    },
+                    db,
+                )?;
+                if duplicate.tags {
+                    self.set_note_tags(copies[&note.id].0, &note.tags, db)?;
+                }
+            }
+
+            self.note(copies[&id].0, db)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, new_note, parse, test_user};
+
+    #[test]
+    fn test_rewrite_id_links() {
+        let ids = vec![(1, (10, String::new())), (2, (20, String::new()))]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            rewrite_id_links("/note/1 and /disambiguation/2,3,1. /note/4", &ids),
+            "/note/10 and /disambiguation/20,3,10. /note/4"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_title_links() {
+        let links = vec![
+            ("crypt".to_owned(), CopyLink::Id(7)),
+            (
+                "dungeon".to_owned(),
+                CopyLink::Title("Dungeon (copy)".to_owned()),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            rewrite_title_links(
+                "[[ crypt ]], [[Crypt| the crypt ]], ![[Crypt]], [[Dungeon|lobby]], [[Trap]]",
+                &links
+            ),
+            "[crypt](/note/7), [the crypt](/note/7), ![[Crypt]], [[Dungeon (copy)|lobby]], [[Trap]]"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_note() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let dungeon = new_note(
+                &user,
+                "Dungeon",
+                "Start in [[Dungeon|the lobby]].",
+                None,
+                &db,
+            );
+            let room = new_note(
+                &user,
+                "Room 1",
+                "Watch for the [[trap]].",
+                Some(dungeon),
+                &db,
+            );
+            let trap = new_note(
+                &user,
+                "Trap",
+                &format!(
+                    "Back to /note/{} or /note/{}. Falls into [[Crypt|the crypt]].",
+                    room, dungeon
+                ),
+                Some(room),
+                &db,
+            );
+            user.set_note_tags(trap, &["danger".to_owned()], &db)
+                .unwrap();
+            user.update_note(trap, &parse(r#"{ "pinned": true }"#), &db)
+                .unwrap();
+            let crypt = new_note(&user, "Crypt", "", Some(dungeon), &db);
+            user.reorder_children(dungeon, &[crypt, room], &db).unwrap();
+
+            let copy = user
+                .duplicate_note(
+                    dungeon,
+                    &DuplicateNotePayload {
+                        deep: true,
+                        tags: true,
+                        ..DuplicateNotePayload::default()
+                    },
+                    &db,
+                )
+                .unwrap();
+            assert_eq!(copy.title, "Dungeon (copy)");
+            assert_eq!(copy.parent_note_id, None);
+            assert_eq!(copy.body, "Start in [[Dungeon (copy)|the lobby]].");
+
+            let copies = user.descendants(copy.id, &db).unwrap();
+            let copy_of = |title: &str| copies.iter().find(|n| n.title == title).unwrap();
+            assert_eq!(copies.len(), 3);
+            assert_eq!(
+                user.note_tree(Some(copy.id), Some(1), &db).unwrap()[0]
+                    .children
+                    .iter()
+                    .map(|t| t.note.title.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["Crypt", "Room 1"]
+            );
+            let trap_copy = copy_of("Trap");
+            assert_eq!(
+                trap_copy.body,
+                format!(
+                    "Back to /note/{} or /note/{}. Falls into [the crypt](/note/{}).",
+                    copy_of("Room 1").id,
+                    copy.id,
+                    copy_of("Crypt").id
+                )
+            );
+            assert_eq!(
+                copy_of("Room 1").body,
+                format!("Watch for the [trap](/note/{}).", trap_copy.id)
+            );
+            assert_eq!(trap_copy.tags, vec!["danger"]);
+            assert!(trap_copy.pinned);
+            assert_eq!(
+                user.note(trap, &db).unwrap().body,
+                format!(
+                    "Back to /note/{} or /note/{}. Falls into [[Crypt|the crypt]].",
+                    room, dungeon
+                )
+            );
+
+            let copy = user
+                .duplicate_note(dungeon, &DuplicateNotePayload::default(), &db)
+                .unwrap();
+            assert_eq!(copy.title, "Dungeon (copy 2)");
+            assert_eq!(user.subtree_count(copy.id, &db).unwrap(), 0);
+
+            // A copy elsewhere keeps its title if it is free there, and leaves the tags behind.
+            let copy = user
+                .duplicate_note(
+                    trap,
+                    &DuplicateNotePayload {
+                        parent_note_id: Some(Some(dungeon)),
+                        ..DuplicateNotePayload::default()
+                    },
+                    &db,
+                )
+                .unwrap();
+            assert_eq!(copy.title, "Trap");
+            assert_eq!(copy.parent_note_id, Some(dungeon));
+            assert!(copy.tags.is_empty());
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,594 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The link index. Each note's `[[Title]]` links and plain mentions of other notes' titles are
+//! parsed when it is saved and stored in `note_links`. The migration that created the table
+//! back-fills it with SQL patterns that follow the same rules as the parsing here. Aliases are
+//! matched just like titles.
+
+use super::{load_tags, Conn, Note, NoteWithTags, UpdateNotePayload, User, WithTags};
+use crate::{
+    error::{DbError, Result},
+    schema::{note_aliases, note_links, notes},
+};
+use diesel::{
+    sql_types::{Integer, Text},
+    Connection, ExpressionMethods, JoinOnDsl, NullableExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::ops::Range;
+
+// The full text index finds the candidates, which the regex then checks for whole-word matches.
+// Names made only of stop words give an empty text query, which would match nothing, so those
+// fall back to the regex alone.
+const MENTIONING_NOTES: &str = r#"
+    SELECT notes.* FROM notes
+    INNER JOIN note_search ON note_search.note_id = notes.id,
+         phraseto_tsquery('english', $2) query
+    WHERE notes.user_id = $1
+      AND (numnode(query) = 0 OR note_search.document @@ query)
+      AND notes.body ~* $3
+"#;
+
+#[derive(Insertable)]
+#[table_name = "note_links"]
+struct NewNoteLink {
+    source_note_id: i32,
+    target_note_id: Option<i32>,
+    target_title: String,
+    explicit: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteLink {
+    /// The note on the other end of the link.
+    pub note: NoteWithTags,
+    /// `true` for a `[[Title]]` link, `false` for a plain mention of the title.
+    pub explicit: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct RenameNotePayload {
+    pub title: String,
+    /// Only report what would change, without saving anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteRename {
+    pub note: NoteWithTags,
+    /// The notes whose `[[links]]` to the old title were pointed at the new one, sorted by title.
+    pub rewritten: Vec<NoteWithTags>,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Where the target of each `[[Target]]` or `[[Target|label]]` link is in `body`, untrimmed.
+pub(super) fn link_targets(body: &str) -> Vec<Range<usize>> {
+    let mut targets = vec![];
+    let mut offset = 0;
+    while let Some(start) = body[offset..].find("[[") {
+        offset += start + 2;
+        let end = match body[offset..].find(']') {
+            Some(end) => offset + end,
+            None => break,
+        };
+        if !body[end..].starts_with("]]") {
+            continue;
+        }
+
+        let target_end = body[offset..end].find('|').map_or(end, |bar| offset + bar);
+        targets.push(offset..target_end);
+        offset = end + 2;
+    }
+    targets
+}
+
+/// The titles that `body` links to, as written. Repeats that only differ in case are left out.
+pub(super) fn explicit_links(body: &str) -> Vec<String> {
+    let mut links = Vec::<String>::new();
+    for target in link_targets(body) {
+        let target = body[target].trim();
+        if !target.is_empty()
+            && !links
+                .iter()
+                .any(|l| l.to_lowercase() == target.to_lowercase())
+        {
+            links.push(target.to_owned());
+        }
+    }
+    links
+}
+
+/// `body` with every link to `from` pointed at `to` instead, keeping any labels. `None` if there
+/// were no such links. `from` must already be lowercase.
+pub(super) fn rewrite_links(body: &str, from: &str, to: &str) -> Option<String> {
+    let mut rewritten = String::with_capacity(body.len());
+    let mut copied = 0;
+    for target in link_targets(body) {
+        if body[target.clone()].trim().to_lowercase() == from {
+            rewritten.push_str(&body[copied..target.start]);
+            rewritten.push_str(to);
+            copied = target.end;
+        }
+    }
+
+    if copied == 0 {
+        None
+    } else {
+        rewritten.push_str(&body[copied..]);
+        Some(rewritten)
+    }
+}
+
+/// Whether `title` appears in `body` as whole words. Both must already be lowercase.
+fn mentions(body: &str, title: &str) -> bool {
+    if title.trim().is_empty() {
+        return false;
+    }
+
+    body.match_indices(title).any(|(i, _)| {
+        let before = body[..i].chars().next_back();
+        let after = body[i + title.len()..].chars().next();
+        !before.into_iter().chain(after).any(is_word_char)
+    })
+}
+
+/// A case-insensitive regex matching `name` as whole words, following the same rules as
+/// `mentions`. Every `[[link]]` to the name matches it as well.
+fn mention_pattern(name: &str) -> String {
+    let mut pattern = String::from("(^|[^[:alnum:]_])");
+    for c in name.chars() {
+        // In Postgres regexes a backslash before anything but a letter or digit is a literal.
+        if !c.is_alphanumeric() {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str("($|[^[:alnum:]_])");
+    pattern
+}
+
+fn note_links(found: Vec<(Note, bool)>, db: &Conn) -> Result<Vec<NoteLink>> {
+    let (found, explicit): (Vec<_>, Vec<_>) = found.into_iter().unzip();
+    Ok(load_tags(found, db)?
+        .into_iter()
+        .zip(explicit)
+        .map(|(note, explicit)| NoteLink { note, explicit })
+        .collect())
+}
+
+impl User {
+    /// Every title and alias of the user's notes, as `(note id, lowercase name, name)`. Notes in
+    /// the trash are included, so that links to them work again once they are restored.
+    fn link_names(&self, db: &Conn) -> Result<Vec<(i32, String, String)>> {
+        let mut names = notes::table
+            .filter(notes::user_id.eq(self.id))
+            .select((notes::id, notes::title))
+            .load::<(i32, String)>(db)?;
+        names.extend(
+            note_aliases::table
+                .inner_join(notes::table)
+                .filter(notes::user_id.eq(self.id))
+                .select((notes::id, note_aliases::alias))
+                .load::<(i32, String)>(db)?,
+        );
+        Ok(names
+            .into_iter()
+            .filter(|(_, name)| !name.trim().is_empty())
+            .map(|(id, name)| (id, name.to_lowercase(), name))
+            .collect())
+    }
+
+    /// Rebuilds the index of everything `note` links to or mentions.
+    pub(super) fn index_links_from(&self, note: &Note, db: &Conn) -> Result<()> {
+        self.index_links_among(note, &self.link_names(db)?, db)
+    }
+
+    fn index_links_among(
+        &self,
+        note: &Note,
+        names: &[(i32, String, String)],
+        db: &Conn,
+    ) -> Result<()> {
+        diesel::delete(note_links::table.filter(note_links::source_note_id.eq(note.id)))
+            .execute(db)?;
+
+        let names = names
+            .iter()
+            .filter(|(id, _, _)| *id != note.id)
+            .collect::<Vec<_>>();
+        let mut own_names = note_aliases::table
+            .filter(note_aliases::note_id.eq(note.id))
+            .select(note_aliases::alias)
+            .load::<String>(db)?;
+        own_names.push(note.title.clone());
+        let own_names = own_names
+            .into_iter()
+            .map(|name| name.to_lowercase())
+            .collect::<Vec<_>>();
+
+        let mut links = vec![];
+        for target in explicit_links(&note.body) {
+            let lower = target.to_lowercase();
+            if own_names.contains(&lower) {
+                continue;
+            }
+
+            let mut matches = names
+                .iter()
+                .filter(|(_, name, _)| *name == lower)
+                .map(|(id, _, _)| Some(*id))
+                .collect::<Vec<_>>();
+            matches.sort_unstable();
+            matches.dedup();
+            if matches.is_empty() {
+                links.push(NewNoteLink {
+                    source_note_id: note.id,
+                    target_note_id: None,
+                    target_title: target,
+                    explicit: true,
+                });
+            } else {
+                links.extend(matches.into_iter().map(|target_note_id| NewNoteLink {
+                    source_note_id: note.id,
+                    target_note_id,
+                    target_title: target.clone(),
+                    explicit: true,
+                }));
+            }
+        }
+
+        let body = note.body.to_lowercase();
+        for (id, lower, name) in names {
+            if mentions(&body, lower) && !links.iter().any(|l| l.target_note_id == Some(*id)) {
+                links.push(NewNoteLink {
+                    source_note_id: note.id,
+                    target_note_id: Some(*id),
+                    target_title: name.clone(),
+                    explicit: false,
+                });
+            }
+        }
+
+        diesel::insert_into(note_links::table)
+            .values(&links)
+            .execute(db)?;
+        Ok(())
+    }
+
+    /// Re-indexes every note that links to `note`, either by one of its current names or by an
+    /// old one.
+    pub(super) fn index_links_to(&self, note: &Note, db: &Conn) -> Result<()> {
+        let mut names = note_aliases::table
+            .filter(note_aliases::note_id.eq(note.id))
+            .select(note_aliases::alias)
+            .load::<String>(db)?;
+        names.push(note.title.clone());
+
+        let mut sources = notes::table
+            .filter(notes::user_id.eq(self.id))
+            .filter(
+                notes::id.eq_any(
+                    note_links::table
+                        .filter(note_links::target_note_id.eq(note.id))
+                        .select(note_links::source_note_id),
+                ),
+            )
+            .load::<Note>(db)?;
+        for name in names.iter().filter(|n| !n.trim().is_empty()) {
+            sources.extend(
+                diesel::sql_query(MENTIONING_NOTES)
+                    .bind::<Integer, _>(self.id)
+                    .bind::<Text, _>(name)
+                    .bind::<Text, _>(mention_pattern(name))
+                    .load::<Note>(db)?,
+            );
+        }
+        sources.retain(|source| source.id != note.id);
+        sources.sort_by_key(|source| source.id);
+        sources.dedup_by_key(|source| source.id);
+
+        let names = self.link_names(db)?;
+        for source in sources {
+            self.index_links_among(&source, &names, db)?;
+        }
+        Ok(())
+    }
+
+    /// Renames a note and rewrites the `[[links]]` to it in the rest of the user's notes, all in
+    /// one transaction. A dry run reports the same changes without saving any of them.
+    pub fn rename_note(
+        &self,
+        id: i32,
+        rename: &RenameNotePayload,
+        db: &Conn,
+    ) -> Result<NoteRename> {
+        db.transaction(|| {
+            let mut note = self.authorize_note(id, db)?;
+            self.authorize_title(Some(id), &rename.title, note.parent_note_id, db)?;
+
+            let old_title = note.title.to_lowercase();
+            let rewritten = notes::table
+                .filter(notes::user_id.eq(self.id))
+                .filter(
+                    notes::id.eq_any(
+                        note_links::table
+                            .filter(note_links::target_note_id.eq(id))
+                            .filter(note_links::explicit.eq(true))
+                            .select(note_links::source_note_id),
+                    ),
+                )
+                .order((notes::title, notes::id))
+                .for_update()
+                .load::<Note>(db)?
+                .into_iter()
+                .filter_map(|mut source| {
+                    source.body = rewrite_links(&source.body, &old_title, &rename.title)?;
+                    Some(source)
+                })
+                .collect::<Vec<_>>();
+
+            if rename.dry_run {
+                note.title = rename.title.clone();
+                return Ok(NoteRename {
+                    note: note.with_tags(db).ok_or(DbError::NotFound)?,
+                    rewritten: load_tags(rewritten, db)?,
+                });
+            }
+
+            for source in &rewritten {
+                diesel::update(notes::table.find(source.id))
+                    .set(notes::body.eq(&source.body))
+                    .execute(db)?;
+            }
+            // Re-indexes the rewritten notes too, now that they mention the new title.
+            let note = self.update_note(
+                id,
+                &UpdateNotePayload {
+                    title: Some(rename.title.clone()),
+                    ..UpdateNotePayload::default()
+                },
+                db,
+            )?;
+
+            Ok(NoteRename {
+                note,
+                rewritten: load_tags(
+                    notes::table
+                        .filter(
+                            notes::id.eq_any(rewritten.iter().map(|n| n.id).collect::<Vec<_>>()),
+                        )
+                        .order((notes::title, notes::id))
+                        .load::<Note>(db)?,
+                    db,
+                )?,
+            })
+        })
+    }
+
+    /// The notes that this one links to or mentions, sorted by title.
+    pub fn links(&self, id: i32, db: &Conn) -> Result<Vec<NoteLink>> {
+        self.live_notes()
+            .find(id)
+            .select(notes::id)
+            .first::<i32>(db)?;
+        let found = note_links::table
+            .inner_join(notes::table.on(notes::id.nullable().eq(note_links::target_note_id)))
+            .filter(note_links::source_note_id.eq(id))
+            .filter(notes::deleted_at.is_null())
+            .select((notes::all_columns, note_links::explicit))
+            .order((notes::title, notes::id))
+            .load::<(Note, bool)>(db)?;
+
+        note_links(found, db)
+    }
+
+    /// The notes that link to or mention this one, sorted by title.
+    pub fn backlinks(&self, id: i32, db: &Conn) -> Result<Vec<NoteLink>> {
+        self.live_notes()
+            .find(id)
+            .select(notes::id)
+            .first::<i32>(db)?;
+        let found = note_links::table
+            .inner_join(notes::table.on(notes::id.eq(note_links::source_note_id)))
+            .filter(note_links::target_note_id.eq(id))
+            .filter(notes::deleted_at.is_null())
+            .select((notes::all_columns, note_links::explicit))
+            .order((notes::title, notes::id))
+            .load::<(Note, bool)>(db)?;
+
+        note_links(found, db)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, new_note, parse, test_user};
+    use diesel::Connection;
+
+    fn linked(links: Vec<NoteLink>) -> Vec<(String, bool)> {
+        links
+            .into_iter()
+            .map(|l| (l.note.title, l.explicit))
+            .collect()
+    }
+
+    #[test]
+    fn test_explicit_links() {
+        assert_eq!(
+            explicit_links("See [[Strahd]], [[ Castle Ravenloft | his castle]] and [[strahd]]."),
+            vec!["Strahd", "Castle Ravenloft"]
+        );
+        assert_eq!(
+            explicit_links("[[]] [[a]b]] [[Barovia"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_mention_pattern() {
+        assert_eq!(
+            mention_pattern("C++ (2e)"),
+            r"(^|[^[:alnum:]_])C\+\+\ \(2e\)($|[^[:alnum:]_])"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        assert_eq!(
+            rewrite_links(
+                "Ask [[Bob the Bartender]] or [[ bob the bartender |Bob]]. Bob knows.",
+                "bob the bartender",
+                "Robert Thornwood"
+            ),
+            Some("Ask [[Robert Thornwood]] or [[Robert Thornwood|Bob]]. Bob knows.".to_owned())
+        );
+        assert_eq!(
+            rewrite_links("[[Bob]]", "bob the bartender", "Robert"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_note_links() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let strahd = new_note(&user, "Strahd", "Lives in [[Castle Ravenloft]].", None, &db);
+            let castle = new_note(&user, "Castle Ravenloft", "Home of strahd.", None, &db);
+            let village = new_note(
+                &user,
+                "Village of Barovia",
+                "Strahd's spies are everywhere. Strahdville is elsewhere.",
+                None,
+                &db,
+            );
+
+            // The castle didn't exist yet when Strahd was saved, but the link was picked up.
+            assert_eq!(
+                linked(user.links(strahd, &db).unwrap()),
+                vec![("Castle Ravenloft".to_owned(), true)]
+            );
+            assert_eq!(
+                linked(user.backlinks(strahd, &db).unwrap()),
+                vec![
+                    ("Castle Ravenloft".to_owned(), false),
+                    ("Village of Barovia".to_owned(), false)
+                ]
+            );
+
+            user.update_note(village, &parse(r#"{ "body": "Nothing to see." }"#), &db)
+                .unwrap();
+            assert_eq!(user.backlinks(strahd, &db).unwrap().len(), 1);
+
+            // Links follow the title.
+            user.update_note(castle, &parse(r#"{ "title": "Ravenloft Keep" }"#), &db)
+                .unwrap();
+            assert!(user.links(strahd, &db).unwrap().is_empty());
+            user.update_note(castle, &parse(r#"{ "title": "Castle Ravenloft" }"#), &db)
+                .unwrap();
+            assert_eq!(user.links(strahd, &db).unwrap().len(), 1);
+
+            // Names made of stop words or regex characters are still found once they exist.
+            let notes = new_note(
+                &user,
+                "Session Notes",
+                "We fought [[It]] and learned C++. Bitten by an itch.",
+                None,
+                &db,
+            );
+            let it = new_note(&user, "It", "", None, &db);
+            let cpp = new_note(&user, "C++", "", None, &db);
+            assert_eq!(
+                linked(user.backlinks(it, &db).unwrap()),
+                vec![("Session Notes".to_owned(), true)]
+            );
+            assert_eq!(
+                linked(user.backlinks(cpp, &db).unwrap()),
+                vec![("Session Notes".to_owned(), false)]
+            );
+            assert_eq!(user.links(notes, &db).unwrap().len(), 2);
+
+            // Notes in the trash are left out.
+            user.delete_note(castle, &db).unwrap();
+            assert!(user.links(strahd, &db).unwrap().is_empty());
+            assert!(user.backlinks(strahd, &db).unwrap().is_empty());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_rename_note() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let bob = new_note(&user, "Bob the Bartender", "Pours drinks.", None, &db);
+            let inn = new_note(
+                &user,
+                "Yawning Portal",
+                "Run by [[Bob the Bartender|Bob]]. Ask [[bob the bartender]].",
+                None,
+                &db,
+            );
+            let rumors = new_note(
+                &user,
+                "Rumors",
+                "Bob the Bartender hears things.",
+                None,
+                &db,
+            );
+
+            let rename = |dry_run| RenameNotePayload {
+                title: "Robert Thornwood".to_owned(),
+                dry_run,
+            };
+            let preview = user.rename_note(bob, &rename(true), &db).unwrap();
+            assert_eq!(preview.note.title, "Robert Thornwood");
+            assert_eq!(
+                preview.rewritten.iter().map(|n| n.id).collect::<Vec<_>>(),
+                vec![inn]
+            );
+            assert_eq!(
+                preview.rewritten[0].body,
+                "Run by [[Robert Thornwood|Bob]]. Ask [[Robert Thornwood]]."
+            );
+            assert_eq!(user.note(bob, &db).unwrap().title, "Bob the Bartender");
+            assert!(user
+                .note(inn, &db)
+                .unwrap()
+                .body
+                .contains("[[Bob the Bartender|Bob]]"));
+
+            let renamed = user.rename_note(bob, &rename(false), &db).unwrap();
+            assert_eq!(renamed.note.title, "Robert Thornwood");
+            assert_eq!(renamed.rewritten[0].body, preview.rewritten[0].body);
+            assert_eq!(user.note(inn, &db).unwrap().body, preview.rewritten[0].body);
+            // Plain mentions aren't links, so they are left as they were.
+            assert_eq!(
+                user.note(rumors, &db).unwrap().body,
+                "Bob the Bartender hears things."
+            );
+            assert_eq!(
+                linked(user.backlinks(bob, &db).unwrap()),
+                vec![("Yawning Portal".to_owned(), true)]
+            );
+
+            Ok(())
+        });
+    }
+}
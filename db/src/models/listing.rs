@@ -0,0 +1,372 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{
+    archive::UNDER_ARCHIVED,
+    load_tags,
+    tagging::{normalize_tag, tag_subtree},
+    Conn, Note, NoteWithTags, User,
+};
+use crate::{
+    error::{DbError, Result},
+    schema::{note_tags_id, notes, tags},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use diesel::{
+    dsl::{not, sql},
+    sql_types::Bool,
+    BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 500;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteSort {
+    /// Alphabetical.
+    Title,
+    /// Newest first.
+    Created,
+    /// Most recently changed first.
+    Updated,
+    /// The manual order of siblings, then by title. Meant for listing the children of one note.
+    Position,
+}
+
+#[derive(Deserialize, Serialize, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteListQuery {
+    pub archived: Option<bool>,
+    pub pinned: Option<bool>,
+    /// Also matches the tags nested under it.
+    pub tag: Option<String>,
+    /// Only the direct children of this note.
+    pub parent: Option<i32>,
+    /// Only notes that are (or aren't) at the top level.
+    pub top_level: Option<bool>,
+    /// Only notes that are (or aren't) somewhere under an archived note.
+    pub under_archived: Option<bool>,
+    pub updated_since: Option<DateTime<Utc>>,
+    /// Defaults to `position` when listing the children of a note, and `updated` otherwise.
+    pub sort: Option<NoteSort>,
+    pub limit: Option<i64>,
+    /// The `next` cursor from the previous page.
+    pub after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NotePage {
+    pub notes: Vec<NoteWithTags>,
+    /// Pass back as `after` to fetch the next page. Missing on the last page.
+    pub next: Option<String>,
+}
+
+/// Cursors are `<id>:<sort key>`, where the key is the title, an RFC 3339 timestamp, or the
+/// position (empty if there is none) and title as `<position>:<title>`.
+fn cursor(sort: NoteSort, note: &Note) -> String {
+    let key = match sort {
+        NoteSort::Title => note.title.clone(),
+        NoteSort::Position => match note.position {
+            Some(position) => format!("{}:{}", position, note.title),
+            None => format!(":{}", note.title),
+        },
+        NoteSort::Created => note.created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        NoteSort::Updated => note.updated_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+    };
+    format!("{}:{}", note.id, key)
+}
+
+fn parse_cursor(cursor: &str) -> Option<(i32, &str)> {
+    let (id, key) = cursor.split_once(':')?;
+    Some((id.parse().ok()?, key))
+}
+
+fn parse_position(key: &str) -> Result<(Option<i32>, &str)> {
+    match key.split_once(':') {
+        Some(("", title)) => Ok((None, title)),
+        Some((position, title)) => position
+            .parse()
+            .map(|position| (Some(position), title))
+            .map_err(|_| DbError::BadRequest("Invalid cursor")),
+        None => Err(DbError::BadRequest("Invalid cursor")),
+    }
+}
+
+fn parse_timestamp(key: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(key)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| DbError::BadRequest("Invalid cursor"))
+}
+
+impl User {
+    pub fn list_notes_page(&self, list: &NoteListQuery, db: &Conn) -> Result<NotePage> {
+        let sort = list.sort.unwrap_or(if list.parent.is_some() {
+            NoteSort::Position
+        } else {
+            NoteSort::Updated
+        });
+        let limit = list
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+
+        let mut query = self.live_notes().into_boxed();
+        if let Some(archived) = list.archived {
+            query = query.filter(notes::archived.eq(archived));
+        }
+        if let Some(pinned) = list.pinned {
+            query = query.filter(notes::pinned.eq(pinned));
+        }
+        if let Some(parent) = list.parent {
+            query = query.filter(notes::parent_note_id.eq(parent));
+        }
+        match list.top_level {
+            Some(true) => query = query.filter(notes::parent_note_id.is_null()),
+            Some(false) => query = query.filter(notes::parent_note_id.is_not_null()),
+            None => {}
+        }
+        match list.under_archived {
+            Some(true) => query = query.filter(sql::<Bool>(UNDER_ARCHIVED)),
+            Some(false) => query = query.filter(not(sql::<Bool>(UNDER_ARCHIVED))),
+            None => {}
+        }
+        if let Some(since) = list.updated_since {
+            query = query.filter(notes::updated_at.gt(since));
+        }
+        if let Some(ref tag) = list.tag {
+            let tag = normalize_tag(tag).unwrap_or_default();
+            query = query.filter(
+                notes::id.eq_any(
+                    note_tags_id::table
+                        .filter(
+                            note_tags_id::tag_id.eq_any(
+                                tags::table
+                                    .filter(tags::user_id.eq(self.id))
+                                    .filter(tag_subtree(&tag))
+                                    .select(tags::id),
+                            ),
+                        )
+                        .select(note_tags_id::note_id),
+                ),
+            );
+        }
+
+        if let Some(ref after) = list.after {
+            let (id, key) = parse_cursor(after).ok_or(DbError::BadRequest("Invalid cursor"))?;
+            query = match sort {
+                NoteSort::Title => query.filter(
+                    notes::title
+                        .gt(key.to_owned())
+                        .or(notes::title.eq(key.to_owned()).and(notes::id.gt(id))),
+                ),
+                NoteSort::Position => {
+                    let (position, title) = parse_position(key)?;
+                    let later_title = notes::title
+                        .gt(title.to_owned())
+                        .or(notes::title.eq(title.to_owned()).and(notes::id.gt(id)));
+                    match position {
+                        // Notes without a position sort last.
+                        Some(position) => query.filter(
+                            notes::position
+                                .gt(position)
+                                .or(notes::position.is_null())
+                                .or(notes::position.eq(position).and(later_title)),
+                        ),
+                        None => query.filter(notes::position.is_null().and(later_title)),
+                    }
+                }
+                NoteSort::Created => {
+                    let key = parse_timestamp(key)?;
+                    query.filter(
+                        notes::created_at
+                            .lt(key)
+                            .or(notes::created_at.eq(key).and(notes::id.lt(id))),
+                    )
+                }
+                NoteSort::Updated => {
+                    let key = parse_timestamp(key)?;
+                    query.filter(
+                        notes::updated_at
+                            .lt(key)
+                            .or(notes::updated_at.eq(key).and(notes::id.lt(id))),
+                    )
+                }
+            };
+        }
+
+        query = match sort {
+            NoteSort::Title => query.order((notes::title, notes::id)),
+            NoteSort::Position => query.order((notes::position, notes::title, notes::id)),
+            NoteSort::Created => query.order((notes::created_at.desc(), notes::id.desc())),
+            NoteSort::Updated => query.order((notes::updated_at.desc(), notes::id.desc())),
+        };
+
+        // Fetch one extra to find out whether there is another page.
+        let mut found = query.limit(limit + 1).load::<Note>(db)?;
+        let next = if found.len() as i64 > limit {
+            found.truncate(limit as usize);
+            found.last().map(|note| cursor(sort, note))
+        } else {
+            None
+        };
+
+        Ok(NotePage {
+            notes: load_tags(found, db)?,
+            next,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, parse, test_user};
+    use diesel::Connection;
+
+    fn titles(page: &NotePage) -> Vec<&str> {
+        page.notes.iter().map(|n| n.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_notes_page() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let mut ids = vec![];
+            for title in &[
+                "Baldur's Gate",
+                "Neverwinter",
+                "Waterdeep",
+                "Luskan",
+                "Candlekeep",
+            ] {
+                ids.push(
+                    user.new_note(
+                        &parse(&format!(r#"{{ "title": "{}", "body": "" }}"#, title)),
+                        &db,
+                    )
+                    .unwrap()
+                    .id,
+                );
+            }
+            user.update_note(ids[1], &parse(r#"{ "pinned": true }"#), &db)
+                .unwrap();
+            user.update_note(ids[2], &parse(r#"{ "archived": true }"#), &db)
+                .unwrap();
+            user.set_note_tags(ids[3], &["location/city".to_owned()], &db)
+                .unwrap();
+
+            let mut query = NoteListQuery {
+                sort: Some(NoteSort::Title),
+                limit: Some(2),
+                ..NoteListQuery::default()
+            };
+            let page = user.list_notes_page(&query, &db).unwrap();
+            assert_eq!(titles(&page), vec!["Baldur's Gate", "Candlekeep"]);
+            query.after = page.next;
+            let page = user.list_notes_page(&query, &db).unwrap();
+            assert_eq!(titles(&page), vec!["Luskan", "Neverwinter"]);
+            query.after = page.next;
+            let page = user.list_notes_page(&query, &db).unwrap();
+            assert_eq!(titles(&page), vec!["Waterdeep"]);
+            assert_eq!(page.next, None);
+
+            // An empty page would look like the last one.
+            let page = user
+                .list_notes_page(
+                    &NoteListQuery {
+                        sort: Some(NoteSort::Title),
+                        limit: Some(0),
+                        ..NoteListQuery::default()
+                    },
+                    &db,
+                )
+                .unwrap();
+            assert_eq!(titles(&page), vec!["Baldur's Gate"]);
+            assert!(page.next.is_some());
+
+            // Everything was created in the same transaction, so this falls back to the ids.
+            let page = user
+                .list_notes_page(
+                    &NoteListQuery {
+                        sort: Some(NoteSort::Created),
+                        limit: Some(4),
+                        ..NoteListQuery::default()
+                    },
+                    &db,
+                )
+                .unwrap();
+            assert_eq!(
+                page.notes.iter().map(|n| n.id).collect::<Vec<_>>(),
+                ids.iter().rev().take(4).copied().collect::<Vec<_>>()
+            );
+
+            let filtered = |query: NoteListQuery| {
+                user.list_notes_page(&query, &db)
+                    .unwrap()
+                    .notes
+                    .into_iter()
+                    .map(|n| n.id)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                filtered(NoteListQuery {
+                    pinned: Some(true),
+                    ..NoteListQuery::default()
+                }),
+                vec![ids[1]]
+            );
+            assert_eq!(
+                filtered(NoteListQuery {
+                    archived: Some(true),
+                    ..NoteListQuery::default()
+                }),
+                vec![ids[2]]
+            );
+            assert_eq!(
+                filtered(NoteListQuery {
+                    tag: Some("location".to_owned()),
+                    ..NoteListQuery::default()
+                }),
+                vec![ids[3]]
+            );
+            assert_eq!(
+                filtered(NoteListQuery {
+                    parent: Some(ids[0]),
+                    ..NoteListQuery::default()
+                }),
+                Vec::<i32>::new()
+            );
+            assert_eq!(
+                filtered(NoteListQuery {
+                    top_level: Some(true),
+                    ..NoteListQuery::default()
+                })
+                .len(),
+                ids.len()
+            );
+
+            assert!(matches!(
+                user.list_notes_page(
+                    &NoteListQuery {
+                        after: Some("garbage".to_owned()),
+                        ..NoteListQuery::default()
+                    },
+                    &db
+                ),
+                Err(DbError::BadRequest(_))
+            ));
+
+            Ok(())
+        });
+    }
+}
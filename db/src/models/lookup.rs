@@ -0,0 +1,194 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{escape_like, load_tags, Conn, Note, NoteWithTags, User};
+use crate::{
+    error::{DbError, Result},
+    schema::{note_aliases, notes},
+};
+use diesel::{
+    sql_types::{BigInt, Integer, Text},
+    BoolExpressionMethods, ExpressionMethods, PgTextExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const MAX_COMPLETIONS: i64 = 50;
+
+// Shorter names come first, so that typing more of a name quickly narrows it down.
+const AUTOCOMPLETE: &str = r#"
+    SELECT DISTINCT ON (length(name), lower(name)) name FROM (
+        SELECT notes.title AS name FROM notes
+        WHERE notes.user_id = $1 AND notes.deleted_at IS NULL
+        UNION ALL
+        SELECT note_aliases.alias FROM note_aliases
+        INNER JOIN notes ON notes.id = note_aliases.note_id
+        WHERE notes.user_id = $1 AND notes.deleted_at IS NULL
+    ) names
+    WHERE name ILIKE $2
+    ORDER BY length(name), lower(name), name
+    LIMIT $3
+"#;
+
+#[derive(QueryableByName)]
+struct Completion {
+    #[sql_type = "Text"]
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TitleLookup {
+    /// Only one note goes by the title.
+    Note { note: NoteWithTags },
+    /// Several notes go by the title, sorted by title.
+    Disambiguation { notes: Vec<NoteWithTags> },
+}
+
+/// `notes` by lowercase title and alias, the way `[[links]]` resolve.
+pub(super) fn by_name(notes: &[NoteWithTags]) -> HashMap<String, Vec<&NoteWithTags>> {
+    let mut by_name = HashMap::<_, Vec<_>>::new();
+    for note in notes {
+        for name in std::iter::once(&note.title).chain(&note.aliases) {
+            let named = by_name.entry(name.to_lowercase()).or_default();
+            if !named.iter().any(|n: &&NoteWithTags| n.id == note.id) {
+                named.push(note);
+            }
+        }
+    }
+    by_name
+}
+
+/// Keeps only the notes with a title or alias that `matches`, unless none of them have one.
+fn narrow(found: &mut Vec<NoteWithTags>, matches: impl Fn(&str) -> bool) {
+    let named = |n: &NoteWithTags| {
+        std::iter::once(&n.title)
+            .chain(&n.aliases)
+            .any(|name| matches(name))
+    };
+    if found.iter().any(&named) {
+        found.retain(|n| named(n));
+    }
+}
+
+impl User {
+    /// Finds the notes with `title` as their title or one of their aliases. Exact matches win over
+    /// ones that only differ in case, which win over names that merely start with `title`.
+    pub fn lookup_title(&self, title: &str, db: &Conn) -> Result<TitleLookup> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(DbError::NotFound);
+        }
+
+        let pattern = format!("{}%", escape_like(title));
+        let found = self
+            .live_notes()
+            .filter(
+                notes::title.ilike(&pattern).or(notes::id.eq_any(
+                    note_aliases::table
+                        .filter(note_aliases::alias.ilike(&pattern))
+                        .select(note_aliases::note_id),
+                )),
+            )
+            .order((notes::title, notes::id))
+            .load::<Note>(db)?;
+
+        let lower = title.to_lowercase();
+        let mut found = load_tags(found, db)?;
+        narrow(&mut found, |name| name.to_lowercase() == lower);
+        narrow(&mut found, |name| name == title);
+
+        match found.len() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(TitleLookup::Note {
+                note: found.remove(0),
+            }),
+            _ => Ok(TitleLookup::Disambiguation { notes: found }),
+        }
+    }
+
+    /// Up to `limit` titles and aliases starting with `prefix`, ignoring case, shortest first.
+    /// `limit` is clamped to between 1 and 50.
+    pub fn autocomplete(&self, prefix: &str, limit: i64, db: &Conn) -> Result<Vec<String>> {
+        Ok(diesel::sql_query(AUTOCOMPLETE)
+            .bind::<Integer, _>(self.id)
+            .bind::<Text, _>(format!("{}%", escape_like(prefix.trim_start())))
+            .bind::<BigInt, _>(limit.clamp(1, MAX_COMPLETIONS))
+            .load::<Completion>(db)?
+            .into_iter()
+            .map(|c| c.name)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::AliasPayload;
+    use crate::testing::{db, new_note, test_user};
+    use diesel::Connection;
+
+    fn lookup(user: &User, title: &str, db: &Conn) -> Vec<i32> {
+        match user.lookup_title(title, db) {
+            Ok(TitleLookup::Note { note }) => vec![note.id],
+            Ok(TitleLookup::Disambiguation { notes }) => {
+                let mut ids = notes.iter().map(|n| n.id).collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            }
+            Err(DbError::NotFound) => vec![],
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_lookup_title() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let strahd = new_note(&user, "Strahd", "", None, &db);
+            let lower = new_note(&user, "strahd", "", None, &db);
+            let castle = new_note(&user, "Castle Ravenloft", "", None, &db);
+            let village = new_note(&user, "Barovia Village", "", None, &db);
+            let barovia = new_note(&user, "Barovia", "", None, &db);
+            user.add_alias(
+                castle,
+                &AliasPayload {
+                    alias: "Strahd's Castle".to_owned(),
+                },
+                &db,
+            )
+            .unwrap();
+
+            assert_eq!(lookup(&user, "Strahd", &db), vec![strahd]);
+            assert_eq!(lookup(&user, "strahd", &db), vec![lower]);
+            assert_eq!(lookup(&user, "STRAHD", &db), vec![strahd, lower]);
+            assert_eq!(lookup(&user, "strahd's", &db), vec![castle]);
+            assert_eq!(lookup(&user, "castle", &db), vec![castle]);
+            assert_eq!(lookup(&user, "barov", &db), vec![village, barovia]);
+            assert_eq!(lookup(&user, "Barovia", &db), vec![barovia]);
+            assert_eq!(lookup(&user, "%", &db), Vec::<i32>::new());
+            assert_eq!(lookup(&user, " ", &db), Vec::<i32>::new());
+
+            user.delete_note(lower, &db).unwrap();
+
+            assert_eq!(
+                user.autocomplete("str", 10, &db).unwrap(),
+                vec!["Strahd", "Strahd's Castle"]
+            );
+            assert_eq!(
+                user.autocomplete("", 3, &db).unwrap(),
+                vec!["Strahd", "Barovia", "Barovia Village"]
+            );
+            assert_eq!(user.autocomplete("", -1, &db).unwrap(), vec!["Strahd"]);
+
+            Ok(())
+        });
+    }
+}
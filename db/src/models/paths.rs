@@ -0,0 +1,158 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Subtree queries backed by `note_paths`, which a trigger keeps in step with `parent_note_id`.
+
+use super::{load_tags, Conn, Note, NoteWithTags, User};
+use crate::{error::Result, schema::notes};
+use diesel::{
+    sql_types::{BigInt, Integer},
+    QueryDsl, RunQueryDsl,
+};
+
+// Sorting by path lists every note before the notes under it.
+const DESCENDANTS: &str = r#"
+    SELECT notes.* FROM notes
+    INNER JOIN note_paths ON note_paths.note_id = notes.id
+    WHERE notes.user_id = $1
+      AND notes.deleted_at IS NULL
+      AND notes.id <> $2
+      AND note_paths.path <@ (SELECT path FROM note_paths WHERE note_id = $2)
+    ORDER BY note_paths.path
+"#;
+
+const ANCESTORS: &str = r#"
+    SELECT notes.* FROM notes
+    INNER JOIN note_paths ON note_paths.note_id = notes.id
+    WHERE notes.user_id = $1
+      AND notes.deleted_at IS NULL
+      AND notes.id <> $2
+      AND note_paths.path @> (SELECT path FROM note_paths WHERE note_id = $2)
+    ORDER BY nlevel(note_paths.path)
+"#;
+
+const SUBTREE_COUNT: &str = r#"
+    SELECT count(*) AS count FROM notes
+    INNER JOIN note_paths ON note_paths.note_id = notes.id
+    WHERE notes.user_id = $1
+      AND notes.deleted_at IS NULL
+      AND notes.id <> $2
+      AND note_paths.path <@ (SELECT path FROM note_paths WHERE note_id = $2)
+"#;
+
+#[derive(QueryableByName)]
+struct SubtreeCount {
+    #[sql_type = "BigInt"]
+    count: i64,
+}
+
+impl User {
+    /// Everything under the note, at any depth. Parents come before their children.
+    pub fn descendants(&self, id: i32, db: &Conn) -> Result<Vec<NoteWithTags>> {
+        self.live_notes()
+            .find(id)
+            .select(notes::id)
+            .first::<i32>(db)?;
+        let found = diesel::sql_query(DESCENDANTS)
+            .bind::<Integer, _>(self.id)
+            .bind::<Integer, _>(id)
+            .load::<Note>(db)?;
+
+        load_tags(found, db)
+    }
+
+    /// The notes above this one, starting from the top level, for breadcrumbs.
+    pub fn ancestors(&self, id: i32, db: &Conn) -> Result<Vec<NoteWithTags>> {
+        self.live_notes()
+            .find(id)
+            .select(notes::id)
+            .first::<i32>(db)?;
+        let found = diesel::sql_query(ANCESTORS)
+            .bind::<Integer, _>(self.id)
+            .bind::<Integer, _>(id)
+            .load::<Note>(db)?;
+
+        load_tags(found, db)
+    }
+
+    /// How many notes are under this one, at any depth.
+    pub fn subtree_count(&self, id: i32, db: &Conn) -> Result<i64> {
+        self.live_notes()
+            .find(id)
+            .select(notes::id)
+            .first::<i32>(db)?;
+        Ok(diesel::sql_query(SUBTREE_COUNT)
+            .bind::<Integer, _>(self.id)
+            .bind::<Integer, _>(id)
+            .get_result::<SubtreeCount>(db)?
+            .count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        error::DbError,
+        testing::{db, new_note, test_user},
+    };
+    use diesel::Connection;
+
+    fn titles(notes: Vec<NoteWithTags>) -> Vec<String> {
+        notes.into_iter().map(|n| n.title).collect()
+    }
+
+    #[test]
+    fn test_note_paths() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let coast = new_note(&user, "Sword Coast", "", None, &db);
+            let waterdeep = new_note(&user, "Waterdeep", "", Some(coast), &db);
+            let portal = new_note(&user, "Yawning Portal", "", Some(waterdeep), &db);
+            let cellar = new_note(&user, "Undermountain", "", Some(portal), &db);
+            let dale = new_note(&user, "Icewind Dale", "", None, &db);
+
+            assert_eq!(
+                titles(user.ancestors(cellar, &db).unwrap()),
+                vec!["Sword Coast", "Waterdeep", "Yawning Portal"]
+            );
+            assert_eq!(
+                titles(user.descendants(coast, &db).unwrap()),
+                vec!["Waterdeep", "Yawning Portal", "Undermountain"]
+            );
+            assert_eq!(user.subtree_count(coast, &db).unwrap(), 3);
+            assert_eq!(user.subtree_count(cellar, &db).unwrap(), 0);
+
+            // Moving a note carries its whole subtree along.
+            user.move_note(waterdeep, Some(dale), &db).unwrap();
+            assert_eq!(
+                titles(user.ancestors(cellar, &db).unwrap()),
+                vec!["Icewind Dale", "Waterdeep", "Yawning Portal"]
+            );
+            assert_eq!(user.subtree_count(coast, &db).unwrap(), 0);
+            assert_eq!(user.subtree_count(dale, &db).unwrap(), 3);
+
+            user.move_note(portal, None, &db).unwrap();
+            assert_eq!(
+                titles(user.ancestors(cellar, &db).unwrap()),
+                vec!["Yawning Portal"]
+            );
+
+            // Trashed notes drop out.
+            user.delete_note(cellar, &db).unwrap();
+            assert_eq!(user.subtree_count(portal, &db).unwrap(), 0);
+            assert!(matches!(
+                user.ancestors(cellar, &db),
+                Err(DbError::NotFound)
+            ));
+
+            Ok(())
+        });
+    }
+}
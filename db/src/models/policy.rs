@@ -0,0 +1,201 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Ownership checks that every note mutation goes through before it touches the database.
+//!
+//! A note the user can't see is reported as `DbError::NotFound`, exactly as if it didn't exist.
+//! A note the user can see but points at something it may not use, such as another user's note or
+//! a trashed note as its parent, is `DbError::Forbidden`.
+
+use super::{Conn, Note, Tag, User, WithTags};
+use crate::{
+    error::{DbError, Result},
+    schema::{notes, tags},
+};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use std::collections::HashSet;
+
+impl User {
+    /// Locks one of the user's live notes for the rest of the transaction.
+    pub(super) fn authorize_note(&self, id: i32, db: &Conn) -> Result<Note> {
+        Ok(self.live_notes().find(id).for_update().first::<Note>(db)?)
+    }
+
+    /// Locks one of the user's notes in the trash for the rest of the transaction.
+    pub(super) fn authorize_trashed_note(&self, id: i32, db: &Conn) -> Result<Note> {
+        Ok(notes::table
+            .filter(notes::user_id.eq(self.id))
+            .filter(notes::deleted_at.is_not_null())
+            .find(id)
+            .for_update()
+            .first::<Note>(db)?)
+    }
+
+    /// Locks one of the user's tags for the rest of the transaction.
+    pub(super) fn authorize_tag(&self, id: i32, db: &Conn) -> Result<Tag> {
+        Ok(tags::table
+            .filter(tags::user_id.eq(self.id))
+            .find(id)
+            .for_update()
+            .first::<Tag>(db)?)
+    }
+
+    /// Checks that a note may be placed under `parent_note_id`, or at the top level if `None`.
+    pub(super) fn authorize_parent(&self, parent_note_id: Option<i32>, db: &Conn) -> Result<()> {
+        match parent_note_id {
+            None => Ok(()),
+            Some(parent) => match self.authorize_note(parent, db) {
+                Err(DbError::NotFound) => Err(DbError::Forbidden),
+                result => result.map(|_| ()),
+            },
+        }
+    }
+
+    /// Checks that note `id` may be placed under `parent_note_id` without ending up inside its own
+    /// subtree, where it would no longer be reachable from the top level.
+    pub(super) fn authorize_move(
+        &self,
+        id: i32,
+        parent_note_id: Option<i32>,
+        db: &Conn,
+    ) -> Result<()> {
+        self.authorize_parent(parent_note_id, db)?;
+
+        let mut ancestor = parent_note_id;
+        let mut seen = HashSet::new();
+        while let Some(current) = ancestor {
+            if current == id {
+                return Err(DbError::BadRequest("A note can't be moved under itself"));
+            }
+            if !seen.insert(current) {
+                break;
+            }
+            ancestor = notes::table
+                .find(current)
+                .select(notes::parent_note_id)
+                .first(db)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that note `id`, or a new note if it is `None`, may be called `title` under
+    /// `parent_note_id`, failing with `DbError::TitleConflict` if a sibling outside the trash
+    /// already is.
+    pub(super) fn authorize_title(
+        &self,
+        id: Option<i32>,
+        title: &str,
+        parent_note_id: Option<i32>,
+        db: &Conn,
+    ) -> Result<()> {
+        let mut siblings = notes::table
+            .filter(notes::user_id.eq(self.id))
+            .filter(notes::title.eq(title))
+            .filter(notes::deleted_at.is_null())
+            .into_boxed();
+        if let Some(id) = id {
+            siblings = siblings.filter(notes::id.ne(id));
+        }
+        let sibling = match parent_note_id {
+            Some(parent_note_id) => siblings.filter(notes::parent_note_id.eq(parent_note_id)),
+            None => siblings.filter(notes::parent_note_id.is_null()),
+        }
+        .first::<Note>(db)
+        .optional()?;
+
+        match sibling {
+            Some(sibling) => Err(DbError::TitleConflict(Box::new(
+                sibling.with_tags(db).ok_or(DbError::NotFound)?,
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, other_user, parse, test_user};
+    use diesel::Connection;
+
+    #[test]
+    fn test_cross_user_mutations() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let note = user
+                .new_note(&parse(r#"{ "title": "Secret", "body": "" }"#), &db)
+                .unwrap();
+            user.set_note_tags(note.id, &["plot".to_owned()], &db)
+                .unwrap();
+
+            let other = other_user(&db);
+            let own = other
+                .new_note(&parse(r#"{ "title": "Mine", "body": "" }"#), &db)
+                .unwrap();
+
+            assert!(matches!(
+                other.set_note_tags(note.id, &[], &db),
+                Err(DbError::NotFound)
+            ));
+            assert!(matches!(
+                other.update_note(note.id, &parse(r#"{ "title": "Pwned" }"#), &db),
+                Err(DbError::NotFound)
+            ));
+            assert!(matches!(
+                other.delete_note(note.id, &db),
+                Err(DbError::NotFound)
+            ));
+            assert!(matches!(
+                other.new_note(
+                    &parse(&format!(
+                        r#"{{ "title": "Child", "body": "", "parent_note_id": {} }}"#,
+                        note.id
+                    )),
+                    &db
+                ),
+                Err(DbError::Forbidden)
+            ));
+            assert!(matches!(
+                other.update_note(
+                    own.id,
+                    &parse(&format!(r#"{{ "parent_note_id": {} }}"#, note.id)),
+                    &db
+                ),
+                Err(DbError::Forbidden)
+            ));
+
+            let note = user.note(note.id, &db).unwrap();
+            assert_eq!(note.title, "Secret");
+            assert_eq!(note.tags, vec!["plot".to_owned()]);
+
+            // Trashed notes can't be used as parents either.
+            user.delete_note(note.id, &db).unwrap();
+            assert!(matches!(
+                user.new_note(
+                    &parse(&format!(
+                        r#"{{ "title": "Child", "body": "", "parent_note_id": {} }}"#,
+                        note.id
+                    )),
+                    &db
+                ),
+                Err(DbError::Forbidden)
+            ));
+            assert!(matches!(
+                other.purge_note(note.id, &db),
+                Err(DbError::NotFound)
+            ));
+            assert!(matches!(
+                other.restore_note(note.id, &db),
+                Err(DbError::NotFound)
+            ));
+
+            Ok(())
+        });
+    }
+}
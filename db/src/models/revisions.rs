@@ -0,0 +1,197 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{Conn, Note, NoteWithTags, UpdateNotePayload, User};
+use crate::{
+    error::Result,
+    schema::{note_revisions, notes},
+};
+use diesel::{BelongingToDsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Identifiable, Queryable, Serialize, Deserialize, Associations, Debug, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+#[belongs_to(Note)]
+pub struct NoteRevision {
+    pub id: i32,
+    pub note_id: i32,
+    pub title: String,
+    pub body: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+#[schemars(deny_unknown_fields)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(deny_unknown_fields)]
+pub struct RevisionDiff {
+    pub from: i32,
+    pub to: i32,
+    pub title: Vec<DiffLine>,
+    pub body: Vec<DiffLine>,
+}
+
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            op: match change.tag() {
+                ChangeTag::Equal => DiffOp::Equal,
+                ChangeTag::Insert => DiffOp::Insert,
+                ChangeTag::Delete => DiffOp::Delete,
+            },
+            text: change
+                .value()
+                .strip_suffix('\n')
+                .unwrap_or_else(|| change.value())
+                .to_owned(),
+        })
+        .collect()
+}
+
+impl User {
+    pub fn note_revisions(&self, note_id: i32, db: &Conn) -> Result<Vec<NoteRevision>> {
+        let note = self.live_notes().find(note_id).first::<Note>(db)?;
+
+        Ok(NoteRevision::belonging_to(&note)
+            .order(note_revisions::id.desc())
+            .load::<NoteRevision>(db)?)
+    }
+
+    pub fn note_revision(&self, note_id: i32, revision_id: i32, db: &Conn) -> Result<NoteRevision> {
+        Ok(note_revisions::table
+            .inner_join(notes::table)
+            .filter(notes::user_id.eq(self.id))
+            .filter(notes::deleted_at.is_null())
+            .filter(note_revisions::note_id.eq(note_id))
+            .filter(note_revisions::id.eq(revision_id))
+            .select(note_revisions::all_columns)
+            .first::<NoteRevision>(db)?)
+    }
+
+    pub fn diff_revisions(
+        &self,
+        note_id: i32,
+        from: i32,
+        to: i32,
+        db: &Conn,
+    ) -> Result<RevisionDiff> {
+        let old = self.note_revision(note_id, from, db)?;
+        let new = self.note_revision(note_id, to, db)?;
+
+        Ok(RevisionDiff {
+            from,
+            to,
+            title: diff_lines(&old.title, &new.title),
+            body: diff_lines(&old.body, &new.body),
+        })
+    }
+
+    pub fn restore_revision(
+        &self,
+        note_id: i32,
+        revision_id: i32,
+        db: &Conn,
+    ) -> Result<NoteWithTags> {
+        db.transaction(|| {
+            let revision = self.note_revision(note_id, revision_id, db)?;
+
+            self.update_note(
+                note_id,
+                &UpdateNotePayload {
+                    title: Some(revision.title),
+                    body: Some(revision.body),
+                    ..UpdateNotePayload::default()
+                },
+                db,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        error::DbError,
+        testing::{db, other_user, parse, test_user},
+    };
+    use diesel::Connection;
+
+    #[test]
+    fn test_revisions() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let note = user
+                .new_note(&parse(r#"{ "title": "Tavern", "body": "Ale\nMead" }"#), &db)
+                .unwrap();
+            user.update_note(note.id, &parse(r#"{ "body": "Ale\nWine" }"#), &db)
+                .unwrap();
+            // Changes that don't touch the title or body aren't recorded.
+            user.update_note(note.id, &parse(r#"{ "pinned": true }"#), &db)
+                .unwrap();
+
+            let revisions = user.note_revisions(note.id, &db).unwrap();
+            assert_eq!(revisions.len(), 2);
+            let (newest, oldest) = (revisions[0].id, revisions[1].id);
+            assert_eq!(revisions[1].body, "Ale\nMead");
+
+            let diff = user.diff_revisions(note.id, oldest, newest, &db).unwrap();
+            assert_eq!(
+                diff.body,
+                vec![
+                    DiffLine {
+                        op: DiffOp::Equal,
+                        text: "Ale".to_owned()
+                    },
+                    DiffLine {
+                        op: DiffOp::Delete,
+                        text: "Mead".to_owned()
+                    },
+                    DiffLine {
+                        op: DiffOp::Insert,
+                        text: "Wine".to_owned()
+                    },
+                ]
+            );
+
+            let restored = user.restore_revision(note.id, oldest, &db).unwrap();
+            assert_eq!(restored.body, "Ale\nMead");
+            assert!(restored.pinned);
+            assert_eq!(user.note_revisions(note.id, &db).unwrap().len(), 3);
+
+            let other = other_user(&db);
+            assert!(matches!(
+                other.note_revisions(note.id, &db),
+                Err(DbError::NotFound)
+            ));
+            assert!(matches!(
+                other.restore_revision(note.id, oldest, &db),
+                Err(DbError::NotFound)
+            ));
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{load_tags, Conn, Note, NoteWithTags, User};
+use crate::{error::Result, schema::notes};
+use diesel::{
+    sql_types::{BigInt, Float, Integer, Text},
+    ExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const MAX_RESULTS: i64 = 200;
+
+// The inner query ranks and limits the matches so that `ts_headline`, which re-parses the whole
+// body, only runs on the rows that are actually returned.
+const SEARCH_QUERY: &str = r#"
+    SELECT hits.id,
+           hits.rank,
+           ts_headline('english', hits.title, hits.query,
+                       'StartSel=<mark>, StopSel=</mark>, HighlightAll=true') AS title_highlight,
+           ts_headline('english', hits.body, hits.query,
+                       'StartSel=<mark>, StopSel=</mark>, MaxFragments=3, FragmentDelimiter=" ... "')
+               AS body_highlight
+    FROM (
+        SELECT notes.id, notes.title, notes.body, query,
+               ts_rank(note_search.document, query) AS rank
+        FROM notes
+        INNER JOIN note_search ON note_search.note_id = notes.id,
+             plainto_tsquery('english', $2) query
+        WHERE notes.user_id = $1 AND notes.deleted_at IS NULL AND note_search.document @@ query
+        ORDER BY rank DESC, notes.id
+        LIMIT $3
+    ) hits
+    ORDER BY hits.rank DESC, hits.id
+"#;
+
+#[derive(QueryableByName)]
+struct SearchHit {
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Float"]
+    rank: f32,
+    #[sql_type = "Text"]
+    title_highlight: String,
+    #[sql_type = "Text"]
+    body_highlight: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct SearchResult {
+    pub note: NoteWithTags,
+    pub rank: f32,
+    pub title_highlight: String,
+    pub body_highlight: String,
+}
+
+impl User {
+    /// The best matches for `query`, with `limit` clamped to between 1 and 200.
+    pub fn search_notes(&self, query: &str, limit: i64, db: &Conn) -> Result<Vec<SearchResult>> {
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let hits = diesel::sql_query(SEARCH_QUERY)
+            .bind::<Integer, _>(self.id)
+            .bind::<Text, _>(query)
+            .bind::<BigInt, _>(limit.clamp(1, MAX_RESULTS))
+            .load::<SearchHit>(db)?;
+
+        let found = self
+            .live_notes()
+            .filter(notes::id.eq_any(hits.iter().map(|h| h.id).collect::<Vec<_>>()))
+            .load::<Note>(db)?;
+        let mut found = load_tags(found, db)?
+            .into_iter()
+            .map(|n| (n.id, n))
+            .collect::<HashMap<_, _>>();
+
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| {
+                Some(SearchResult {
+                    note: found.remove(&hit.id)?,
+                    rank: hit.rank,
+                    title_highlight: hit.title_highlight,
+                    body_highlight: hit.body_highlight,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::{db, parse, test_user};
+    use diesel::Connection;
+
+    #[test]
+    fn test_search_notes() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            for (title, body) in &[
+                ("Waterdeep", "The city of splendors"),
+                ("Yawning Portal", "A tavern in Waterdeep run by Durnan"),
+                ("Neverwinter", "The jewel of the north"),
+            ] {
+                user.new_note(
+                    &parse(&format!(
+                        r#"{{ "title": "{}", "body": "{}" }}"#,
+                        title, body
+                    )),
+                    &db,
+                )
+                .unwrap();
+            }
+
+            let results = user.search_notes("waterdeep", 10, &db).unwrap();
+            assert_eq!(results.len(), 2);
+            // Title matches are weighted above body matches.
+            assert_eq!(results[0].note.title, "Waterdeep");
+            assert_eq!(results[0].title_highlight, "<mark>Waterdeep</mark>");
+            assert!(results[1].body_highlight.contains("<mark>Waterdeep</mark>"));
+            assert!(results[0].rank > results[1].rank);
+
+            let note = &results[1].note;
+            user.update_note(note.id, &parse(r#"{ "body": "Run by Durnan" }"#), &db)
+                .unwrap();
+            assert_eq!(user.search_notes("waterdeep", 10, &db).unwrap().len(), 1);
+            assert_eq!(user.search_notes("waterdeep", 0, &db).unwrap().len(), 1);
+            assert_eq!(user.search_notes("durnan", -1, &db).unwrap().len(), 1);
+            assert_eq!(user.search_notes("  ", 10, &db).unwrap().len(), 0);
+
+            Ok(())
+        });
+    }
+}
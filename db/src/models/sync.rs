@@ -0,0 +1,170 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{load_tags, Conn, Note, NoteWithTags, User};
+use crate::{
+    error::Result,
+    schema::{note_tombstones, notes},
+};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+/// Timestamps come from `NOW()`, the start of the writing transaction, so a slow transaction can
+/// commit changes that are older than a cursor already handed out. Each sync looks back this far
+/// behind its cursor to catch them.
+const SYNC_OVERLAP_SECONDS: i64 = 60;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteTombstone {
+    pub id: i32,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteChanges {
+    pub notes: Vec<NoteWithTags>,
+    pub deleted: Vec<NoteTombstone>,
+    /// Pass back as `since` to fetch only what changed after this response. Changes from the
+    /// minute before it are sent again.
+    pub cursor: Option<DateTime<Utc>>,
+}
+
+impl User {
+    /// Everything that changed after `since`. Notes moved to the trash are reported as deleted
+    /// alongside the ones that were purged.
+    pub fn note_changes(&self, cursor: Option<DateTime<Utc>>, db: &Conn) -> Result<NoteChanges> {
+        let since = cursor.map(|c| c - Duration::seconds(SYNC_OVERLAP_SECONDS));
+        let changed = {
+            let mut query = self.live_notes().into_boxed();
+            if let Some(since) = since {
+                query = query.filter(notes::updated_at.gt(since));
+            }
+            query.order(notes::updated_at).load::<Note>(db)?
+        };
+
+        let trashed = {
+            let mut query = notes::table
+                .filter(notes::user_id.eq(self.id))
+                .select((notes::id, notes::deleted_at))
+                .into_boxed();
+            query = match since {
+                Some(since) => query.filter(notes::deleted_at.gt(since)),
+                None => query.filter(notes::deleted_at.is_not_null()),
+            };
+            query.load::<(i32, Option<DateTime<Utc>>)>(db)?
+        };
+
+        let purged = {
+            let mut query = note_tombstones::table
+                .filter(note_tombstones::user_id.eq(self.id))
+                .select((note_tombstones::note_id, note_tombstones::deleted_at))
+                .into_boxed();
+            if let Some(since) = since {
+                query = query.filter(note_tombstones::deleted_at.gt(since));
+            }
+            query.load::<(i32, DateTime<Utc>)>(db)?
+        };
+
+        let mut deleted = trashed
+            .into_iter()
+            .filter_map(|(id, deleted_at)| {
+                Some(NoteTombstone {
+                    id,
+                    deleted_at: deleted_at?,
+                })
+            })
+            .chain(
+                purged
+                    .into_iter()
+                    .map(|(id, deleted_at)| NoteTombstone { id, deleted_at }),
+            )
+            .collect::<Vec<_>>();
+        deleted.sort_by_key(|t| (t.deleted_at, t.id));
+
+        let cursor = changed
+            .iter()
+            .map(|n| n.updated_at)
+            .chain(deleted.iter().map(|t| t.deleted_at))
+            .chain(cursor)
+            .max();
+
+        Ok(NoteChanges {
+            notes: load_tags(changed, db)?,
+            deleted,
+            cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, parse, test_user};
+    use diesel::Connection;
+
+    #[test]
+    fn test_note_changes() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let kept = user
+                .new_note(&parse(r#"{ "title": "Kept", "body": "" }"#), &db)
+                .unwrap();
+            let trashed = user
+                .new_note(&parse(r#"{ "title": "Trashed", "body": "" }"#), &db)
+                .unwrap();
+            let purged = user
+                .new_note(&parse(r#"{ "title": "Purged", "body": "" }"#), &db)
+                .unwrap();
+
+            let everything = user.note_changes(None, &db).unwrap();
+            assert_eq!(everything.notes.len(), 3);
+            assert!(everything.deleted.is_empty());
+
+            // Everything in a test runs in one transaction, so NOW() never moves. Pretend the
+            // first sync happened a while ago instead.
+            let since = everything.cursor.unwrap() - chrono::Duration::hours(1);
+            diesel::update(notes::table.filter(notes::user_id.eq(user.id)))
+                .set(notes::updated_at.eq(since - chrono::Duration::hours(1)))
+                .execute(&db)?;
+            assert!(user
+                .note_changes(Some(since), &db)
+                .unwrap()
+                .notes
+                .is_empty());
+
+            // A change that committed late, stamped just before the cursor, still comes through.
+            diesel::update(notes::table.find(kept.id))
+                .set(notes::updated_at.eq(since - chrono::Duration::seconds(30)))
+                .execute(&db)?;
+            let late = user.note_changes(Some(since), &db).unwrap();
+            assert_eq!(late.notes.len(), 1);
+            assert_eq!(late.cursor, Some(since));
+
+            user.set_note_tags(kept.id, &["npc".to_owned()], &db)
+                .unwrap();
+            user.delete_note(trashed.id, &db).unwrap();
+            user.delete_note(purged.id, &db).unwrap();
+            user.purge_note(purged.id, &db).unwrap();
+
+            let changes = user.note_changes(Some(since), &db).unwrap();
+            assert_eq!(changes.notes.len(), 1);
+            assert_eq!(changes.notes[0].tags, vec!["npc".to_owned()]);
+            let mut deleted = changes.deleted.iter().map(|t| t.id).collect::<Vec<_>>();
+            deleted.sort_unstable();
+            assert_eq!(deleted, vec![trashed.id, purged.id]);
+            assert!(changes.cursor.unwrap() > since);
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,549 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{escape_like, load_tags, Conn, Note, NoteWithTags, Tag, User};
+use crate::{
+    error::{DbError, Result},
+    schema::{note_tags_id, notes, tags},
+};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{Eq, Like, Or},
+    sql_types::{Array, BigInt, Integer, Nullable, Text, Timestamptz},
+    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    TextExpressionMethods,
+};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Notes in the trash keep their tags but aren't counted.
+const TAG_SUMMARIES: &str = r#"
+    SELECT tags.id,
+           tags.tag,
+           COUNT(notes.id) AS note_count,
+           MAX(notes.updated_at) AS last_used
+    FROM tags
+    LEFT JOIN note_tags_id ON note_tags_id.tag_id = tags.id
+    LEFT JOIN notes ON notes.id = note_tags_id.note_id AND notes.deleted_at IS NULL
+    WHERE tags.user_id = $1 AND ($2::int IS NULL OR tags.id = $2)
+    GROUP BY tags.id
+    ORDER BY lower(tags.tag), tags.id
+"#;
+
+const TAG_USES: &str = r#"
+    SELECT tags.id, tags.tag, notes.id AS note_id, notes.updated_at
+    FROM tags
+    LEFT JOIN note_tags_id ON note_tags_id.tag_id = tags.id
+    LEFT JOIN notes ON notes.id = note_tags_id.note_id AND notes.deleted_at IS NULL
+    WHERE tags.user_id = $1
+"#;
+
+const RETAG_NOTES: &str = r#"
+    INSERT INTO note_tags_id (note_id, tag_id)
+    SELECT DISTINCT note_id, $1 FROM note_tags_id
+    WHERE tag_id = ANY($2)
+      AND note_id NOT IN (SELECT note_id FROM note_tags_id WHERE tag_id = $1)
+"#;
+
+#[derive(QueryableByName, Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(deny_unknown_fields)]
+pub struct TagSummary {
+    #[sql_type = "Integer"]
+    pub id: i32,
+    #[sql_type = "Text"]
+    pub tag: String,
+    /// How many notes outside the trash carry this tag.
+    #[sql_type = "BigInt"]
+    pub note_count: i64,
+    /// When a note with this tag was last changed.
+    #[sql_type = "Nullable<Timestamptz>"]
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+#[derive(QueryableByName)]
+struct TagUse {
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Text"]
+    tag: String,
+    #[sql_type = "Nullable<Integer>"]
+    note_id: Option<i32>,
+    #[sql_type = "Nullable<Timestamptz>"]
+    updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct TagTotals {
+    id: Option<i32>,
+    notes: HashSet<i32>,
+    last_used: Option<DateTime<Utc>>,
+}
+
+/// A tag with everything nested under it. Counts include the notes tagged with any descendant.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(deny_unknown_fields)]
+pub struct TagNode {
+    /// The last segment of `tag`.
+    pub name: String,
+    pub tag: String,
+    /// Missing when no note uses this tag directly, only tags nested under it.
+    pub id: Option<i32>,
+    pub note_count: i64,
+    pub last_used: Option<DateTime<Utc>>,
+    pub children: Vec<TagNode>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct RenameTagPayload {
+    pub tag: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct RenameTagPathPayload {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct MergeTagsPayload {
+    /// Tags to fold into `into`. They are deleted afterwards.
+    pub tags: Vec<i32>,
+    pub into: i32,
+}
+
+/// Tidies up a tag's path, so `npc//villain/` and `npc/villain` are the same tag. Returns `None`
+/// for tags with nothing in them.
+pub(super) fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+pub(super) type TagSubtree = Or<Eq<tags::tag, String>, Like<tags::tag, String>>;
+
+/// Matches `tag` and every tag nested under it.
+pub(super) fn tag_subtree(tag: &str) -> TagSubtree {
+    tags::tag
+        .eq(tag.to_owned())
+        .or(tags::tag.like(format!("{}/%", escape_like(tag))))
+}
+
+fn tag_nodes(
+    parent: Option<&str>,
+    totals: &HashMap<String, TagTotals>,
+    children: &HashMap<Option<&str>, Vec<&str>>,
+) -> Vec<TagNode> {
+    let mut paths = children.get(&parent).cloned().unwrap_or_default();
+    paths.sort_by_key(|path| (path.to_lowercase(), *path));
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let total = &totals[path];
+            TagNode {
+                name: path.rsplit('/').next().unwrap_or(path).to_owned(),
+                tag: path.to_owned(),
+                id: total.id,
+                note_count: total.notes.len() as i64,
+                last_used: total.last_used,
+                children: tag_nodes(Some(path), totals, children),
+            }
+        })
+        .collect()
+}
+
+impl User {
+    /// The user's tags arranged by their `/`-separated paths.
+    pub fn tag_tree(&self, db: &Conn) -> Result<Vec<TagNode>> {
+        let uses = diesel::sql_query(TAG_USES)
+            .bind::<Integer, _>(self.id)
+            .load::<TagUse>(db)?;
+
+        let mut totals = HashMap::<String, TagTotals>::new();
+        for tag_use in uses {
+            let mut path = String::new();
+            for segment in tag_use.tag.split('/') {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(segment);
+
+                let total = totals.entry(path.clone()).or_default();
+                total.notes.extend(tag_use.note_id);
+                total.last_used = total.last_used.max(tag_use.updated_at);
+            }
+            totals.entry(path).or_default().id = Some(tag_use.id);
+        }
+
+        let mut children = HashMap::<_, Vec<_>>::new();
+        for path in totals.keys() {
+            let parent = path.rsplit_once('/').map(|(parent, _)| parent);
+            children.entry(parent).or_default().push(path.as_str());
+        }
+
+        Ok(tag_nodes(None, &totals, &children))
+    }
+
+    /// Notes tagged with `tag` or anything nested under it.
+    pub fn notes_tagged(&self, tag: &str, db: &Conn) -> Result<Vec<NoteWithTags>> {
+        let tag = match normalize_tag(tag) {
+            Some(tag) => tag,
+            None => return Ok(vec![]),
+        };
+
+        let tagged = note_tags_id::table
+            .filter(
+                note_tags_id::tag_id.eq_any(
+                    tags::table
+                        .filter(tags::user_id.eq(self.id))
+                        .filter(tag_subtree(&tag))
+                        .select(tags::id),
+                ),
+            )
+            .select(note_tags_id::note_id);
+
+        load_tags(
+            self.live_notes()
+                .filter(notes::id.eq_any(tagged))
+                .load::<Note>(db)?,
+            db,
+        )
+    }
+
+    pub fn list_tags(&self, db: &Conn) -> Result<Vec<TagSummary>> {
+        Ok(diesel::sql_query(TAG_SUMMARIES)
+            .bind::<Integer, _>(self.id)
+            .bind::<Nullable<Integer>, _>(None::<i32>)
+            .load::<TagSummary>(db)?)
+    }
+
+    pub fn tag(&self, id: i32, db: &Conn) -> Result<TagSummary> {
+        diesel::sql_query(TAG_SUMMARIES)
+            .bind::<Integer, _>(self.id)
+            .bind::<Nullable<Integer>, _>(Some(id))
+            .load::<TagSummary>(db)?
+            .pop()
+            .ok_or(DbError::NotFound)
+    }
+
+    /// Renames a tag, and everything nested under it, on every note. Renaming it to one of the
+    /// user's other tags merges the two.
+    pub fn rename_tag(&self, id: i32, new_name: &str, db: &Conn) -> Result<TagSummary> {
+        db.transaction::<_, DbError, _>(|| {
+            let tag = self.authorize_tag(id, db)?;
+            let renamed = self.move_tags(&tag.tag, new_name, db)?;
+            self.tag(renamed.unwrap_or(id), db)
+        })
+    }
+
+    /// Like `rename_tag`, but also works for a path that only exists as the prefix of other tags.
+    pub fn rename_tag_path(&self, from: &str, to: &str, db: &Conn) -> Result<Vec<TagNode>> {
+        db.transaction::<_, DbError, _>(|| {
+            let from = normalize_tag(from).ok_or(DbError::NotFound)?;
+            self.move_tags(&from, to, db)?;
+            self.tag_tree(db)
+        })
+    }
+
+    /// Moves every tag at or under `from` to the same place under `to`. Returns the id of the tag
+    /// that ends up at `to`, if `from` itself was a tag.
+    fn move_tags(&self, from: &str, to: &str, db: &Conn) -> Result<Option<i32>> {
+        let to = normalize_tag(to).ok_or(DbError::BadRequest("Tags can't be empty"))?;
+        if to.starts_with(&format!("{}/", from)) {
+            return Err(DbError::BadRequest("A tag can't be moved under itself"));
+        }
+
+        let moving = tags::table
+            .filter(tags::user_id.eq(self.id))
+            .filter(tag_subtree(from))
+            .for_update()
+            .load::<Tag>(db)?;
+        if moving.is_empty() {
+            return Err(DbError::NotFound);
+        }
+
+        let mut renamed = None;
+        for tag in moving {
+            let target = format!("{}{}", to, &tag.tag[from.len()..]);
+            let existing = tags::table
+                .filter(tags::user_id.eq(self.id))
+                .filter(tags::tag.eq(&target))
+                .filter(tags::id.ne(tag.id))
+                .select(tags::id)
+                .first::<i32>(db)
+                .optional()?;
+
+            let id = match existing {
+                Some(into) => {
+                    self.merge_tags(&[tag.id], into, db)?;
+                    into
+                }
+                None => {
+                    self.touch_tagged_notes(&[tag.id], db)?;
+                    diesel::update(tags::table.find(tag.id))
+                        .set(tags::tag.eq(&target))
+                        .execute(db)?;
+                    tag.id
+                }
+            };
+            if tag.tag == from {
+                renamed = Some(id);
+            }
+        }
+
+        Ok(renamed)
+    }
+
+    /// Moves every note tagged with any of `ids` over to `into`, then deletes the old tags.
+    pub fn merge_tags(&self, ids: &[i32], into: i32, db: &Conn) -> Result<TagSummary> {
+        db.transaction::<_, DbError, _>(|| {
+            self.authorize_tag(into, db)?;
+            let ids = ids
+                .iter()
+                .copied()
+                .filter(|&id| id != into)
+                .collect::<Vec<_>>();
+            for &id in &ids {
+                self.authorize_tag(id, db)?;
+            }
+
+            self.touch_tagged_notes(&ids, db)?;
+            diesel::sql_query(RETAG_NOTES)
+                .bind::<Integer, _>(into)
+                .bind::<Array<Integer>, _>(&ids)
+                .execute(db)?;
+            self.remove_tags(&ids, db)?;
+
+            self.tag(into, db)
+        })
+    }
+
+    /// Removes a tag from every note and deletes it.
+    pub fn delete_tag(&self, id: i32, db: &Conn) -> Result<()> {
+        db.transaction::<_, DbError, _>(|| {
+            self.authorize_tag(id, db)?;
+
+            self.touch_tagged_notes(&[id], db)?;
+            self.remove_tags(&[id], db)
+        })
+    }
+
+    fn touch_tagged_notes(&self, tag_ids: &[i32], db: &Conn) -> Result<()> {
+        let tagged = note_tags_id::table
+            .filter(note_tags_id::tag_id.eq_any(tag_ids))
+            .select(note_tags_id::note_id)
+            .load::<i32>(db)?;
+        self.touch_notes(&tagged, db)?;
+
+        Ok(())
+    }
+
+    fn remove_tags(&self, ids: &[i32], db: &Conn) -> Result<()> {
+        diesel::delete(note_tags_id::table.filter(note_tags_id::tag_id.eq_any(ids))).execute(db)?;
+        diesel::delete(
+            tags::table
+                .filter(tags::user_id.eq(self.id))
+                .filter(tags::id.eq_any(ids)),
+        )
+        .execute(db)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, other_user, parse, test_user};
+
+    #[test]
+    fn test_manage_tags() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let mut notes = vec![];
+            for (title, tags) in &[
+                ("Durnan", vec!["npc", "tavern"]),
+                ("Volo", vec!["character"]),
+                ("Yawning Portal", vec!["tavern", "location"]),
+            ] {
+                let note = user
+                    .new_note(
+                        &parse(&format!(r#"{{ "title": "{}", "body": "" }}"#, title)),
+                        &db,
+                    )
+                    .unwrap();
+                let tags = tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                notes.push(user.set_note_tags(note.id, &tags, &db).unwrap());
+            }
+            let id_of = |name: &str, db: &Conn| {
+                user.list_tags(db)
+                    .unwrap()
+                    .into_iter()
+                    .find(|t| t.tag == name)
+                    .unwrap()
+                    .id
+            };
+
+            let tags = user.list_tags(&db).unwrap();
+            assert_eq!(
+                tags.iter()
+                    .map(|t| (t.tag.as_str(), t.note_count))
+                    .collect::<Vec<_>>(),
+                vec![("character", 1), ("location", 1), ("npc", 1), ("tavern", 2)]
+            );
+            assert!(tags.iter().all(|t| t.last_used.is_some()));
+
+            let merged = user
+                .merge_tags(&[id_of("character", &db)], id_of("npc", &db), &db)
+                .unwrap();
+            assert_eq!(merged.note_count, 2);
+            assert_eq!(user.note(notes[1].id, &db).unwrap().tags, vec!["npc"]);
+            assert!(user.note(notes[1].id, &db).unwrap().version > notes[1].version);
+
+            let renamed = user
+                .rename_tag(id_of("location", &db), "place", &db)
+                .unwrap();
+            assert_eq!(renamed.tag, "place");
+            assert_eq!(
+                user.note(notes[2].id, &db).unwrap().tags,
+                vec!["place", "tavern"]
+            );
+
+            // Renaming onto an existing tag merges them.
+            let renamed = user.rename_tag(id_of("place", &db), "tavern", &db).unwrap();
+            assert_eq!(renamed.note_count, 2);
+            assert_eq!(user.note(notes[2].id, &db).unwrap().tags, vec!["tavern"]);
+
+            user.delete_tag(id_of("tavern", &db), &db).unwrap();
+            assert_eq!(user.note(notes[0].id, &db).unwrap().tags, vec!["npc"]);
+            assert_eq!(user.list_tags(&db).unwrap().len(), 1);
+
+            let other = other_user(&db);
+            let npc = id_of("npc", &db);
+            assert!(matches!(other.tag(npc, &db), Err(DbError::NotFound)));
+            assert!(matches!(other.delete_tag(npc, &db), Err(DbError::NotFound)));
+            assert!(matches!(
+                other.rename_tag(npc, "stolen", &db),
+                Err(DbError::NotFound)
+            ));
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_hierarchical_tags() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let mut notes = vec![];
+            for (title, tags) in &[
+                ("Manshoon", vec!["npc/villain", "npc"]),
+                ("Durnan", vec!["npc/ally/"]),
+                ("Waterdeep", vec!["location//city"]),
+                ("Npc_Roster", vec!["npc_roster"]),
+            ] {
+                let note = user
+                    .new_note(
+                        &parse(&format!(r#"{{ "title": "{}", "body": "" }}"#, title)),
+                        &db,
+                    )
+                    .unwrap();
+                let tags = tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                notes.push(user.set_note_tags(note.id, &tags, &db).unwrap());
+            }
+            assert_eq!(notes[1].tags, vec!["npc/ally"]);
+            assert_eq!(notes[2].tags, vec!["location/city"]);
+
+            let mut tagged = user
+                .notes_tagged("npc", &db)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.title)
+                .collect::<Vec<_>>();
+            tagged.sort();
+            assert_eq!(tagged, vec!["Durnan", "Manshoon"]);
+            assert_eq!(user.notes_tagged("npc/villain", &db).unwrap().len(), 1);
+            // `_` is a LIKE wildcard, so make sure it isn't treated as one.
+            assert!(user.notes_tagged("np_", &db).unwrap().is_empty());
+
+            let tree = user.tag_tree(&db).unwrap();
+            assert_eq!(
+                tree.iter().map(|n| n.tag.as_str()).collect::<Vec<_>>(),
+                vec!["location", "npc", "npc_roster"]
+            );
+            let location = &tree[0];
+            assert_eq!(location.id, None);
+            assert_eq!(location.note_count, 1);
+            assert_eq!(location.children[0].name, "city");
+            let npc = &tree[1];
+            assert!(npc.id.is_some());
+            // Manshoon is tagged twice under `npc` but only counted once.
+            assert_eq!(npc.note_count, 2);
+            assert_eq!(
+                npc.children
+                    .iter()
+                    .map(|n| (n.name.as_str(), n.note_count))
+                    .collect::<Vec<_>>(),
+                vec![("ally", 1), ("villain", 1)]
+            );
+
+            // Renaming a parent renames everything under it.
+            let renamed = user.rename_tag(npc.id.unwrap(), "character", &db).unwrap();
+            assert_eq!(renamed.tag, "character");
+            assert_eq!(
+                user.note(notes[0].id, &db).unwrap().tags,
+                vec!["character", "character/villain"]
+            );
+            assert_eq!(
+                user.note(notes[1].id, &db).unwrap().tags,
+                vec!["character/ally"]
+            );
+            assert_eq!(
+                user.note(notes[3].id, &db).unwrap().tags,
+                vec!["npc_roster"]
+            );
+
+            // Paths that aren't tags themselves can be renamed too, merging where they overlap.
+            user.rename_tag_path("location", "character/ally", &db)
+                .unwrap();
+            assert_eq!(
+                user.note(notes[2].id, &db).unwrap().tags,
+                vec!["character/ally/city"]
+            );
+            user.rename_tag_path("character/ally/city", "character/ally", &db)
+                .unwrap();
+            assert_eq!(user.notes_tagged("character/ally", &db).unwrap().len(), 2);
+
+            assert!(matches!(
+                user.rename_tag_path("character", "character/nested", &db),
+                Err(DbError::BadRequest(_))
+            ));
+            assert!(matches!(
+                user.rename_tag_path("missing", "found", &db),
+                Err(DbError::NotFound)
+            ));
+
+            Ok(())
+        });
+    }
+}
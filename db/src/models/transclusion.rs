@@ -0,0 +1,295 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `![[Title]]` embeds another note's body in place, e.g. a shared price list on every tavern.
+
+use super::{links::link_targets, lookup::by_name, Conn, NoteWithTags, User};
+use crate::error::{DbError, Result};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::HashMap, ops::Range};
+
+/// The deepest embeds are expanded, whatever the caller asks for.
+pub const MAX_EMBED_DEPTH: u32 = 8;
+/// Past either limit, the remaining embeds are left as written.
+const MAX_EMBEDS: usize = 1_000;
+const MAX_EXPANDED_LEN: usize = 1 << 20;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ExpandedNote {
+    pub note: NoteWithTags,
+    /// The body with every `![[Title]]` replaced by that note's body, itself expanded.
+    pub body: String,
+    /// The embeds that were left as written, in the order they appear. A note embedded more than
+    /// once only reports its own embeds the first time.
+    pub unresolved: Vec<UnresolvedEmbed>,
+    /// Whether expansion stopped early because the body grew too large. The embeds it skipped are
+    /// reported as `too_large`.
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+#[schemars(deny_unknown_fields)]
+pub struct UnresolvedEmbed {
+    /// The note containing the embed, which may itself have been embedded.
+    pub note_id: i32,
+    pub title: String,
+    pub reason: EmbedProblem,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedProblem {
+    Missing,
+    Ambiguous,
+    Cycle,
+    TooDeep,
+    TooLarge,
+}
+
+/// Each `![[Target]]` in `body`, with where the whole embed is and where its target is.
+fn embeds(body: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    link_targets(body)
+        .into_iter()
+        .filter_map(|target| {
+            if !body[..target.start - 2].ends_with('!') {
+                return None;
+            }
+            let end = target.end + body[target.end..].find("]]")? + 2;
+            Some((target.start - 3..end, target))
+        })
+        .collect()
+}
+
+struct Expander<'a> {
+    by_name: HashMap<String, Vec<&'a NoteWithTags>>,
+    max_depth: u32,
+    /// The notes being expanded, outermost first.
+    stack: Vec<i32>,
+    unresolved: Vec<UnresolvedEmbed>,
+    /// Expanded bodies that don't depend on where the note was embedded from.
+    expanded: HashMap<i32, String>,
+    embeds: usize,
+    len: usize,
+    truncated: bool,
+}
+
+impl Expander<'_> {
+    fn expand(&mut self, note: &NoteWithTags) -> String {
+        self.stack.push(note.id);
+        let body = &note.body;
+        let mut expanded = String::with_capacity(body.len());
+        let mut copied = 0;
+        for (embed, target) in embeds(body) {
+            let title = body[target].trim();
+            let problem = match self.by_name.get(&title.to_lowercase()).map(Vec::as_slice) {
+                None => Err(EmbedProblem::Missing),
+                Some([target]) if self.stack.contains(&target.id) => Err(EmbedProblem::Cycle),
+                Some([_]) if self.stack.len() > self.max_depth as usize => {
+                    Err(EmbedProblem::TooDeep)
+                }
+                Some([_]) if self.truncated || self.embeds >= MAX_EMBEDS => {
+                    self.truncated = true;
+                    Err(EmbedProblem::TooLarge)
+                }
+                Some([target]) => Ok(*target),
+                Some(_) => Err(EmbedProblem::Ambiguous),
+            };
+
+            let problem = problem.and_then(|target| {
+                self.embeds += 1;
+                let inner = self.expand_once(target);
+                if self.len + inner.len() > MAX_EXPANDED_LEN {
+                    self.truncated = true;
+                    return Err(EmbedProblem::TooLarge);
+                }
+                self.len += inner.len();
+                Ok(inner)
+            });
+
+            match problem {
+                Ok(inner) => {
+                    expanded.push_str(&body[copied..embed.start]);
+                    expanded.push_str(&inner);
+                    copied = embed.end;
+                }
+                Err(reason) => self.unresolved.push(UnresolvedEmbed {
+                    note_id: note.id,
+                    title: title.to_owned(),
+                    reason,
+                }),
+            }
+        }
+        expanded.push_str(&body[copied..]);
+        self.stack.pop();
+        expanded
+    }
+
+    /// Expands each note at most once, unless what came out depended on the embeds around it.
+    fn expand_once(&mut self, note: &NoteWithTags) -> String {
+        if let Some(expanded) = self.expanded.get(&note.id) {
+            return expanded.clone();
+        }
+
+        let reported = self.unresolved.len();
+        let expanded = self.expand(note);
+        if self.unresolved[reported..]
+            .iter()
+            .all(|u| matches!(u.reason, EmbedProblem::Missing | EmbedProblem::Ambiguous))
+        {
+            self.expanded.insert(note.id, expanded.clone());
+        }
+        expanded
+    }
+}
+
+impl User {
+    /// The note with its `![[embeds]]` expanded, `max_depth` levels deep (at most
+    /// [`MAX_EMBED_DEPTH`]). Embeds that can't be expanded are left as written and reported
+    /// instead.
+    pub fn expanded_note(&self, id: i32, max_depth: u32, db: &Conn) -> Result<ExpandedNote> {
+        let notes = self.list_notes(db)?;
+        let note = notes.iter().find(|n| n.id == id).ok_or(DbError::NotFound)?;
+
+        let mut expander = Expander {
+            by_name: by_name(&notes),
+            max_depth: max_depth.min(MAX_EMBED_DEPTH),
+            stack: vec![],
+            unresolved: vec![],
+            expanded: HashMap::new(),
+            embeds: 0,
+            len: 0,
+            truncated: false,
+        };
+        let body = expander.expand(note);
+
+        Ok(ExpandedNote {
+            note: note.clone(),
+            body,
+            unresolved: expander.unresolved,
+            truncated: expander.truncated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{db, new_note, test_user};
+    use diesel::Connection;
+
+    #[test]
+    fn test_embeds() {
+        let body = "![[A]] [[B]] é![[ C | label ]] ![[D]";
+        assert_eq!(
+            embeds(body)
+                .into_iter()
+                .map(|(embed, target)| (&body[embed], &body[target]))
+                .collect::<Vec<_>>(),
+            vec![("![[A]]", "A"), ("![[ C | label ]]", " C ")]
+        );
+    }
+
+    #[test]
+    fn test_expanded_note() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            new_note(
+                &user,
+                "Tavern Prices",
+                "Ale: 4cp ![[House Rules]]",
+                None,
+                &db,
+            );
+            new_note(&user, "House Rules", "No brawling.", None, &db);
+            let inn = new_note(
+                &user,
+                "Yawning Portal",
+                "Prices:\n![[tavern prices]]\n![[Menu]]",
+                None,
+                &db,
+            );
+
+            let expanded = user.expanded_note(inn, 8, &db).unwrap();
+            assert_eq!(expanded.body, "Prices:\nAle: 4cp No brawling.\n![[Menu]]");
+            assert_eq!(
+                expanded.unresolved,
+                vec![UnresolvedEmbed {
+                    note_id: inn,
+                    title: "Menu".to_owned(),
+                    reason: EmbedProblem::Missing,
+                }]
+            );
+
+            let expanded = user.expanded_note(inn, 1, &db).unwrap();
+            assert_eq!(
+                expanded.body,
+                "Prices:\nAle: 4cp ![[House Rules]]\n![[Menu]]"
+            );
+            assert_eq!(expanded.unresolved[0].reason, EmbedProblem::TooDeep);
+
+            let a = new_note(&user, "A", "a ![[B]]", None, &db);
+            new_note(&user, "B", "b ![[A]] ![[C]]", None, &db);
+            new_note(&user, "C", "c", None, &db);
+            new_note(&user, "c", "also c", None, &db);
+            let expanded = user.expanded_note(a, 8, &db).unwrap();
+            assert_eq!(expanded.body, "a b ![[A]] ![[C]]");
+            assert_eq!(
+                expanded
+                    .unresolved
+                    .iter()
+                    .map(|u| u.reason)
+                    .collect::<Vec<_>>(),
+                vec![EmbedProblem::Cycle, EmbedProblem::Ambiguous]
+            );
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_expanded_note_diamond() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let map = new_note(&user, "Map", "![[North]] ![[South]]", None, &db);
+            new_note(&user, "North", "n(![[Legend]])", None, &db);
+            new_note(&user, "South", "s(![[Legend]])", None, &db);
+            new_note(&user, "Legend", "legend ![[Key]]", None, &db);
+
+            // The legend is expanded once and reported once, but appears under both.
+            let expanded = user.expanded_note(map, 8, &db).unwrap();
+            assert_eq!(expanded.body, "n(legend ![[Key]]) s(legend ![[Key]])");
+            assert_eq!(expanded.unresolved.len(), 1);
+            assert!(!expanded.truncated);
+
+            // Each level embeds the next one twice, doubling the body every time.
+            let mut level = new_note(&user, "Level 0", &"x".repeat(10_000), None, &db);
+            for depth in 1..=8 {
+                level = new_note(
+                    &user,
+                    &format!("Level {}", depth),
+                    &format!("![[Level {0}]]![[Level {0}]]", depth - 1),
+                    None,
+                    &db,
+                );
+            }
+            let expanded = user.expanded_note(level, 100, &db).unwrap();
+            assert!(expanded.truncated);
+            assert!(expanded.body.len() <= MAX_EXPANDED_LEN + 100);
+            assert!(expanded
+                .unresolved
+                .iter()
+                .all(|u| u.reason == EmbedProblem::TooLarge));
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{load_tags, Conn, Note, NoteWithTags, User};
+use crate::{
+    error::{DbError, Result},
+    schema::{note_links, notes},
+};
+use chrono::{DateTime, Utc};
+use crypto::{hmac::Hmac, mac::Mac, sha2::Sha256, util::fixed_time_eq};
+use diesel::{sql_types::Integer, Connection, ExpressionMethods, JoinOnDsl, QueryDsl, RunQueryDsl};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+const TRASH_SUBTREE: &str = r#"
+    WITH RECURSIVE subtree AS (
+        SELECT id FROM notes WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        UNION
+        SELECT notes.id FROM notes
+        INNER JOIN subtree ON notes.parent_note_id = subtree.id
+        WHERE notes.deleted_at IS NULL
+    )
+    UPDATE notes SET deleted_at = statement_timestamp() WHERE id IN (SELECT id FROM subtree)
+"#;
+
+// Only the descendants that were trashed along with the note (same `deleted_at`) come back;
+// anything deleted on its own beforehand stays in the trash. Trashed ancestors are restored too,
+// otherwise the note would be unreachable.
+const RESTORED_NOTES: &str = r#"
+    WITH RECURSIVE target AS (
+        SELECT id, parent_note_id, deleted_at FROM notes
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+    ),
+    subtree AS (
+        SELECT id FROM target
+        UNION
+        SELECT notes.id FROM notes
+        INNER JOIN subtree ON notes.parent_note_id = subtree.id
+        INNER JOIN target ON notes.deleted_at = target.deleted_at
+    ),
+    ancestors AS (
+        SELECT parent_note_id AS id FROM target
+        UNION
+        SELECT notes.parent_note_id FROM notes
+        INNER JOIN ancestors ON notes.id = ancestors.id
+        WHERE notes.deleted_at IS NOT NULL
+    )
+    SELECT notes.* FROM notes
+    WHERE user_id = $2
+      AND deleted_at IS NOT NULL
+      AND (id IN (SELECT id FROM subtree) OR id IN (SELECT id FROM ancestors))
+"#;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct DeletePreview {
+    /// The note and everything under it, parents first.
+    pub notes: Vec<NoteWithTags>,
+    pub tag_links: Vec<NoteTagLink>,
+    /// Links from the rest of the user's notes into the deleted ones, sorted by source title.
+    pub inbound_links: Vec<InboundLink>,
+    /// Pass to the delete endpoint as `confirm`. It stops matching once any of the above changes.
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Eq)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteTagLink {
+    pub note_id: i32,
+    pub tag: String,
+}
+
+#[derive(Queryable, Serialize, Deserialize, JsonSchema, Debug)]
+#[schemars(deny_unknown_fields)]
+pub struct InboundLink {
+    #[serde(skip)]
+    #[schemars(skip)]
+    link_id: i32,
+    pub source_note_id: i32,
+    pub source_title: String,
+    pub target_note_id: Option<i32>,
+    /// `true` for a `[[Title]]` link, `false` for a plain mention of the title.
+    pub explicit: bool,
+}
+
+impl User {
+    /// Everything that deleting the note would take with it.
+    pub fn delete_preview(&self, id: i32, db: &Conn) -> Result<DeletePreview> {
+        let mut notes = vec![self.note(id, db)?];
+        notes.extend(self.descendants(id, db)?);
+        let ids = notes.iter().map(|n| Some(n.id)).collect::<Vec<_>>();
+
+        let tag_links = notes
+            .iter()
+            .flat_map(|n| {
+                n.tags.iter().map(move |tag| NoteTagLink {
+                    note_id: n.id,
+                    tag: tag.clone(),
+                })
+            })
+            .collect();
+        let inbound_links = note_links::table
+            .inner_join(notes::table.on(notes::id.eq(note_links::source_note_id)))
+            .filter(note_links::target_note_id.eq_any(&ids))
+            .filter(notes::deleted_at.is_null())
+            .select((
+                note_links::id,
+                notes::id,
+                notes::title,
+                note_links::target_note_id,
+                note_links::explicit,
+            ))
+            .order((notes::title, notes::id, note_links::id))
+            .load::<InboundLink>(db)?
+            .into_iter()
+            .filter(|l| !ids.contains(&Some(l.source_note_id)))
+            .collect::<Vec<_>>();
+
+        // Edits to the notes, including their tags, bump their versions. Links are re-created
+        // whenever their source is re-indexed. Keying the hash with the password hash, which
+        // never leaves the server, keeps clients from making up a token without a preview.
+        let mut token = Hmac::new(Sha256::new(), self.hashed_password.as_bytes());
+        for note in &notes {
+            token.input(format!("n{}:{};", note.id, note.version).as_bytes());
+        }
+        for link in &inbound_links {
+            token.input(format!("l{};", link.link_id).as_bytes());
+        }
+
+        Ok(DeletePreview {
+            notes,
+            tag_links,
+            inbound_links,
+            token: token
+                .result()
+                .code()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        })
+    }
+
+    /// Moves the note and its descendants to the trash, as long as nothing has changed since the
+    /// delete preview that `token` came from.
+    pub fn confirm_delete(&self, id: i32, token: &str, db: &Conn) -> Result<()> {
+        db.transaction(|| {
+            self.authorize_note(id, db)?;
+            if !fixed_time_eq(
+                self.delete_preview(id, db)?.token.as_bytes(),
+                token.as_bytes(),
+            ) {
+                return Err(DbError::StalePreview);
+            }
+
+            self.delete_note(id, db)
+        })
+    }
+
+    /// Moves the note and all of its descendants to the trash.
+    pub fn delete_note(&self, id: i32, db: &Conn) -> Result<()> {
+        db.transaction(|| {
+            self.authorize_note(id, db)?;
+            diesel::sql_query(TRASH_SUBTREE)
+                .bind::<Integer, _>(id)
+                .bind::<Integer, _>(self.id)
+                .execute(db)?;
+
+            Ok(())
+        })
+    }
+
+    pub fn trash(&self, db: &Conn) -> Result<Vec<NoteWithTags>> {
+        load_tags(
+            notes::table
+                .filter(notes::user_id.eq(self.id))
+                .filter(notes::deleted_at.is_not_null())
+                .order(notes::deleted_at.desc())
+                .load::<Note>(db)?,
+            db,
+        )
+    }
+
+    /// Takes the note out of the trash, failing with `DbError::TitleConflict` if a note that
+    /// would come back has had its title taken in the meantime.
+    pub fn restore_note(&self, id: i32, db: &Conn) -> Result<NoteWithTags> {
+        db.transaction(|| {
+            self.authorize_trashed_note(id, db)?;
+            let restored = diesel::sql_query(RESTORED_NOTES)
+                .bind::<Integer, _>(id)
+                .bind::<Integer, _>(self.id)
+                .load::<Note>(db)?;
+            let ids = restored.iter().map(|n| n.id).collect::<Vec<_>>();
+
+            // Nothing can be added under a trashed note, so only the notes coming back under a
+            // live parent can clash.
+            for note in &restored {
+                let parent = note.parent_note_id;
+                if parent.filter(|parent| ids.contains(parent)).is_none() {
+                    self.authorize_title(Some(note.id), &note.title, parent, db)?;
+                }
+            }
+            diesel::update(notes::table.filter(notes::id.eq_any(&ids)))
+                .set(notes::deleted_at.eq(None::<DateTime<Utc>>))
+                .execute(db)?;
+
+            self.note(id, db)
+        })
+    }
+
+    /// Permanently deletes a note from the trash, along with its subtree and tags.
+    pub fn purge_note(&self, id: i32, db: &Conn) -> Result<()> {
+        db.transaction(|| {
+            let note = self.authorize_trashed_note(id, db)?;
+            diesel::delete(&note).execute(db)?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        error::DbError,
+        schema::note_tags_id,
+        testing::{db, new_note, test_user},
+    };
+
+    #[test]
+    fn test_delete_preview() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let region = new_note(&user, "Sword Coast", "", None, &db);
+            let city = new_note(&user, "Waterdeep", "", Some(region), &db);
+            user.set_note_tags(city, &["city".to_owned()], &db).unwrap();
+            let journal = new_note(
+                &user,
+                "Journal",
+                "Sailed to [[Waterdeep]] along the Sword Coast.",
+                None,
+                &db,
+            );
+
+            let preview = user.delete_preview(region, &db).unwrap();
+            assert_eq!(
+                preview.notes.iter().map(|n| n.id).collect::<Vec<_>>(),
+                vec![region, city]
+            );
+            assert_eq!(
+                preview.tag_links,
+                vec![NoteTagLink {
+                    note_id: city,
+                    tag: "city".to_owned()
+                }]
+            );
+            let mut inbound = preview
+                .inbound_links
+                .iter()
+                .map(|l| (l.source_note_id, l.target_note_id, l.explicit))
+                .collect::<Vec<_>>();
+            inbound.sort_unstable();
+            assert_eq!(
+                inbound,
+                vec![(journal, Some(region), false), (journal, Some(city), true)]
+            );
+
+            // Any change to the subtree invalidates the token.
+            new_note(&user, "Yawning Portal", "", Some(city), &db);
+            assert!(matches!(
+                user.confirm_delete(region, &preview.token, &db),
+                Err(DbError::StalePreview)
+            ));
+            assert_eq!(user.list_notes(&db).unwrap().len(), 4);
+
+            let preview = user.delete_preview(region, &db).unwrap();
+            assert_eq!(preview.notes.len(), 3);
+            assert!(matches!(
+                user.confirm_delete(region, "", &db),
+                Err(DbError::StalePreview)
+            ));
+            user.confirm_delete(region, &preview.token, &db).unwrap();
+            assert_eq!(user.list_notes(&db).unwrap().len(), 1);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let region = new_note(&user, "Sword Coast", "", None, &db);
+            let city = new_note(&user, "Waterdeep", "", Some(region), &db);
+            let tavern = new_note(&user, "Yawning Portal", "", Some(city), &db);
+            let guild = new_note(&user, "Thieves Guild", "", Some(city), &db);
+            user.set_note_tags(guild, &["faction".to_owned()], &db)
+                .unwrap();
+
+            user.delete_note(guild, &db).unwrap();
+            user.delete_note(region, &db).unwrap();
+            assert!(user.list_notes(&db).unwrap().is_empty());
+            assert_eq!(user.trash(&db).unwrap().len(), 4);
+            assert!(matches!(user.note(city, &db), Err(DbError::NotFound)));
+            assert!(matches!(
+                user.delete_note(region, &db),
+                Err(DbError::NotFound)
+            ));
+
+            // Restoring the region brings back what was deleted with it, but not the guild.
+            user.restore_note(region, &db).unwrap();
+            let mut titles = user
+                .list_notes(&db)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.title)
+                .collect::<Vec<_>>();
+            titles.sort();
+            assert_eq!(titles, vec!["Sword Coast", "Waterdeep", "Yawning Portal"]);
+
+            // Restoring a note under a trashed parent restores the parent as well.
+            user.delete_note(city, &db).unwrap();
+            let restored = user.restore_note(tavern, &db).unwrap();
+            assert_eq!(restored.parent_note_id, Some(city));
+            assert!(user.note(city, &db).is_ok());
+
+            // Trashed notes give up their titles, and only get them back if they are still free.
+            user.delete_note(tavern, &db).unwrap();
+            let new_tavern = new_note(&user, "Yawning Portal", "", Some(city), &db);
+            match user.restore_note(tavern, &db) {
+                Err(DbError::TitleConflict(existing)) => assert_eq!(existing.id, new_tavern),
+                _ => panic!("expected a title conflict"),
+            }
+            user.purge_note(tavern, &db).unwrap();
+
+            assert!(matches!(user.purge_note(city, &db), Err(DbError::NotFound)));
+            user.purge_note(guild, &db).unwrap();
+            assert!(user.trash(&db).unwrap().is_empty());
+            assert_eq!(
+                note_tags_id::table
+                    .filter(note_tags_id::note_id.eq(guild))
+                    .count()
+                    .get_result::<i64>(&db)
+                    .unwrap(),
+                0
+            );
+
+            Ok(())
+        });
+    }
+}
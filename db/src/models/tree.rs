@@ -0,0 +1,413 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{load_tags, Conn, Note, NoteWithTags, User};
+use crate::{
+    error::{DbError, Result},
+    schema::notes,
+};
+use diesel::{
+    sql_types::{Integer, Nullable},
+    Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// `path` guards against cycles left behind from before moves were checked.
+const NOTE_TREE: &str = r#"
+    WITH RECURSIVE tree AS (
+        SELECT notes.*, 0 AS depth, ARRAY[notes.id] AS path
+        FROM notes
+        WHERE notes.user_id = $1
+          AND notes.deleted_at IS NULL
+          AND CASE WHEN $2::int IS NULL THEN notes.parent_note_id IS NULL ELSE notes.id = $2 END
+        UNION ALL
+        SELECT notes.*, tree.depth + 1, tree.path || notes.id
+        FROM notes
+        INNER JOIN tree ON notes.parent_note_id = tree.id
+        WHERE notes.deleted_at IS NULL
+          AND notes.id <> ALL(tree.path)
+          AND ($3::int IS NULL OR tree.depth < $3)
+    )
+    SELECT * FROM tree ORDER BY position NULLS LAST, title, id
+"#;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NoteTree {
+    pub note: NoteWithTags,
+    /// In their manual order, then by title.
+    pub children: Vec<NoteTree>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct MoveNotePayload {
+    /// `null` moves the note to the top level.
+    pub parent_note_id: Option<i32>,
+}
+
+fn subtrees(parent: i32, children: &mut HashMap<i32, Vec<NoteWithTags>>) -> Vec<NoteTree> {
+    children
+        .remove(&parent)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|note| NoteTree {
+            children: subtrees(note.id, children),
+            note,
+        })
+        .collect()
+}
+
+impl User {
+    /// The user's notes as a tree, starting from `root` or from the top level. `depth` limits how
+    /// many levels below the starting notes are included.
+    pub fn note_tree(
+        &self,
+        root: Option<i32>,
+        depth: Option<i32>,
+        db: &Conn,
+    ) -> Result<Vec<NoteTree>> {
+        let found = diesel::sql_query(NOTE_TREE)
+            .bind::<Integer, _>(self.id)
+            .bind::<Nullable<Integer>, _>(root)
+            .bind::<Nullable<Integer>, _>(depth)
+            .load::<Note>(db)?;
+
+        if root.is_some() && found.is_empty() {
+            return Err(DbError::NotFound);
+        }
+
+        let mut tops = vec![];
+        let mut children = HashMap::<_, Vec<_>>::new();
+        for note in load_tags(found, db)? {
+            match note.parent_note_id {
+                Some(parent) if Some(note.id) != root => {
+                    children.entry(parent).or_default().push(note)
+                }
+                _ => tops.push(note),
+            }
+        }
+
+        Ok(tops
+            .into_iter()
+            .map(|note| NoteTree {
+                children: subtrees(note.id, &mut children),
+                note,
+            })
+            .collect())
+    }
+
+    /// Moves a note, along with everything under it, to a new parent.
+    pub fn move_note(
+        &self,
+        id: i32,
+        parent_note_id: Option<i32>,
+        db: &Conn,
+    ) -> Result<NoteWithTags> {
+        db.transaction(|| {
+            let note = self.authorize_note(id, db)?;
+            self.authorize_move(id, parent_note_id, db)?;
+            self.authorize_title(Some(id), &note.title, parent_note_id, db)?;
+            diesel::update(self.live_notes().find(id))
+                .set(notes::parent_note_id.eq(parent_note_id))
+                .execute(db)?;
+
+            self.note(id, db)
+        })
+    }
+
+    /// Puts the children of a note in the order of `children`, which must list each of them once.
+    pub fn reorder_children(
+        &self,
+        id: i32,
+        children: &[i32],
+        db: &Conn,
+    ) -> Result<Vec<NoteWithTags>> {
+        db.transaction(|| {
+            self.authorize_note(id, db)?;
+            let mut current = self
+                .live_notes()
+                .filter(notes::parent_note_id.eq(id))
+                .select((notes::id, notes::position))
+                .for_update()
+                .load::<(i32, Option<i32>)>(db)?;
+            current.sort_unstable();
+
+            let mut listed = children.to_vec();
+            listed.sort_unstable();
+            if !listed.iter().eq(current.iter().map(|(id, _)| id)) {
+                return Err(DbError::BadRequest(
+                    "The new order must list every child of the note exactly once",
+                ));
+            }
+
+            let positions = current.into_iter().collect::<HashMap<_, _>>();
+            for (position, child) in (0..).zip(children) {
+                if positions[child] != Some(position) {
+                    diesel::update(self.live_notes().find(child))
+                        .set(notes::position.eq(position))
+                        .execute(db)?;
+                }
+            }
+
+            load_tags(
+                self.live_notes()
+                    .filter(notes::parent_note_id.eq(id))
+                    .order(notes::position)
+                    .load::<Note>(db)?,
+                db,
+            )
+        })
+    }
+
+    /// Follows `titles` down from the top level, one level per title.
+    pub fn note_by_path(&self, titles: &[&str], db: &Conn) -> Result<NoteWithTags> {
+        if titles.is_empty() {
+            return Err(DbError::BadRequest("A path needs at least one title"));
+        }
+
+        let mut parent = None;
+        for (i, title) in titles.iter().enumerate() {
+            let mut child = self
+                .live_notes()
+                .filter(notes::title.eq(title))
+                .select(notes::id)
+                .into_boxed();
+            child = match parent {
+                Some(parent) => child.filter(notes::parent_note_id.eq(parent)),
+                None => child.filter(notes::parent_note_id.is_null()),
+            };
+            parent = Some(
+                child
+                    .first::<i32>(db)
+                    .optional()?
+                    .ok_or_else(|| DbError::PathNotFound(i + 1, (*title).to_owned()))?,
+            );
+        }
+
+        self.note(parent.unwrap(), db)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::NoteListQuery;
+    use crate::testing::{db, new_note, parse, test_user};
+
+    fn titles(trees: &[NoteTree]) -> Vec<&str> {
+        trees.iter().map(|t| t.note.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_note_tree() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let coast = new_note(&user, "Sword Coast", "", None, &db);
+            let waterdeep = new_note(&user, "Waterdeep", "", Some(coast), &db);
+            new_note(&user, "Yawning Portal", "", Some(waterdeep), &db);
+            new_note(&user, "Castle Ward", "", Some(waterdeep), &db);
+            new_note(&user, "Baldur's Gate", "", Some(coast), &db);
+            new_note(&user, "Icewind Dale", "", None, &db);
+            let trashed = new_note(&user, "Luskan", "", Some(coast), &db);
+            user.delete_note(trashed, &db).unwrap();
+
+            let tree = user.note_tree(None, None, &db).unwrap();
+            assert_eq!(titles(&tree), vec!["Icewind Dale", "Sword Coast"]);
+            let coast_tree = &tree[1];
+            assert_eq!(
+                titles(&coast_tree.children),
+                vec!["Baldur's Gate", "Waterdeep"]
+            );
+            assert_eq!(
+                titles(&coast_tree.children[1].children),
+                vec!["Castle Ward", "Yawning Portal"]
+            );
+
+            let tree = user.note_tree(Some(waterdeep), None, &db).unwrap();
+            assert_eq!(titles(&tree), vec!["Waterdeep"]);
+            assert_eq!(tree[0].children.len(), 2);
+
+            let tree = user.note_tree(None, Some(1), &db).unwrap();
+            assert_eq!(tree[1].children.len(), 2);
+            assert!(tree[1].children.iter().all(|t| t.children.is_empty()));
+
+            assert!(matches!(
+                user.note_tree(Some(trashed), None, &db),
+                Err(DbError::NotFound)
+            ));
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_move_note() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let coast = new_note(&user, "Sword Coast", "", None, &db);
+            let waterdeep = new_note(&user, "Waterdeep", "", Some(coast), &db);
+            let portal = new_note(&user, "Yawning Portal", "", Some(waterdeep), &db);
+            let dale = new_note(&user, "Icewind Dale", "", None, &db);
+
+            let moved = user.move_note(waterdeep, Some(dale), &db).unwrap();
+            assert_eq!(moved.parent_note_id, Some(dale));
+            assert_eq!(
+                user.note(portal, &db).unwrap().parent_note_id,
+                Some(waterdeep)
+            );
+
+            for (id, parent) in &[(dale, dale), (dale, waterdeep), (dale, portal)] {
+                assert!(matches!(
+                    user.move_note(*id, Some(*parent), &db),
+                    Err(DbError::BadRequest(_))
+                ));
+                assert!(matches!(
+                    user.update_note(
+                        *id,
+                        &parse(&format!(r#"{{ "parent_note_id": {} }}"#, parent)),
+                        &db
+                    ),
+                    Err(DbError::BadRequest(_))
+                ));
+            }
+
+            let other_waterdeep = new_note(&user, "Waterdeep", "", Some(coast), &db);
+            match user.move_note(other_waterdeep, Some(dale), &db) {
+                Err(DbError::TitleConflict(existing)) => assert_eq!(existing.id, waterdeep),
+                _ => panic!("expected a title conflict"),
+            }
+            let moved = user.move_note(other_waterdeep, None, &db).unwrap();
+            assert_eq!(moved.parent_note_id, None);
+            let moved = user
+                .update_note(portal, &parse(r#"{ "parent_note_id": null }"#), &db)
+                .unwrap();
+            assert_eq!(moved.parent_note_id, None);
+            match user.update_note(
+                other_waterdeep,
+                &parse(r#"{ "title": "Icewind Dale" }"#),
+                &db,
+            ) {
+                Err(DbError::TitleConflict(existing)) => assert_eq!(existing.id, dale),
+                _ => panic!("expected a title conflict"),
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_reorder_children() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let adventure = new_note(&user, "Curse of Strahd", "", None, &db);
+            let arrival = new_note(&user, "Arrival", "", Some(adventure), &db);
+            let village = new_note(&user, "The Village of Barovia", "", Some(adventure), &db);
+            let castle = new_note(&user, "Castle Ravenloft", "", Some(adventure), &db);
+            let ending = new_note(&user, "Epilogue", "", None, &db);
+
+            let reordered = user
+                .reorder_children(adventure, &[arrival, village, castle], &db)
+                .unwrap();
+            assert_eq!(
+                reordered.iter().map(|n| n.id).collect::<Vec<_>>(),
+                vec![arrival, village, castle]
+            );
+            assert_eq!(reordered[2].position, Some(2));
+
+            // Notes that were never ordered come last, by title.
+            user.move_note(ending, Some(adventure), &db).unwrap();
+            let chapters = vec![
+                "Arrival",
+                "The Village of Barovia",
+                "Castle Ravenloft",
+                "Epilogue",
+            ];
+            let tree = user.note_tree(Some(adventure), None, &db).unwrap();
+            assert_eq!(titles(&tree[0].children), chapters);
+
+            // Children are listed in their manual order unless asked otherwise.
+            let mut query = NoteListQuery {
+                parent: Some(adventure),
+                limit: Some(3),
+                ..NoteListQuery::default()
+            };
+            let mut listed = vec![];
+            loop {
+                let page = user.list_notes_page(&query, &db).unwrap();
+                listed.extend(page.notes.into_iter().map(|n| n.title));
+                query.after = page.next;
+                if query.after.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(listed, chapters);
+
+            // A moved note doesn't bring its place along.
+            user.move_note(castle, None, &db).unwrap();
+            user.move_note(castle, Some(adventure), &db).unwrap();
+            assert_eq!(user.note(castle, &db).unwrap().position, None);
+
+            for order in &[
+                vec![arrival, village],
+                vec![arrival, village, castle, ending, arrival],
+                vec![arrival, village, castle, adventure],
+            ] {
+                assert!(matches!(
+                    user.reorder_children(adventure, order, &db),
+                    Err(DbError::BadRequest(_))
+                ));
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_note_by_path() {
+        let db = db().unwrap();
+        db.test_transaction::<_, diesel::result::Error, _>(|| {
+            let user = test_user(&db);
+            let coast = new_note(&user, "Sword Coast", "", None, &db);
+            let waterdeep = new_note(&user, "Waterdeep", "", Some(coast), &db);
+            let portal = new_note(&user, "Yawning Portal", "", Some(waterdeep), &db);
+            new_note(&user, "Yawning Portal", "", None, &db);
+
+            let path = ["Sword Coast", "Waterdeep", "Yawning Portal"];
+            assert_eq!(user.note_by_path(&path, &db).unwrap().id, portal);
+            assert_eq!(user.note_by_path(&path[..1], &db).unwrap().id, coast);
+
+            match user.note_by_path(&["Sword Coast", "Neverwinter", "Yawning Portal"], &db) {
+                Err(DbError::PathNotFound(2, title)) => assert_eq!(title, "Neverwinter"),
+                _ => panic!("expected the second segment to be missing"),
+            }
+            // Titles only match at the top level if the note is there.
+            assert!(matches!(
+                user.note_by_path(&["Waterdeep"], &db),
+                Err(DbError::PathNotFound(1, _))
+            ));
+            assert!(matches!(
+                user.note_by_path(&[], &db),
+                Err(DbError::BadRequest(_))
+            ));
+
+            user.delete_note(waterdeep, &db).unwrap();
+            assert!(matches!(
+                user.note_by_path(&path, &db),
+                Err(DbError::PathNotFound(2, _))
+            ));
+
+            Ok(())
+        });
+    }
+}
@@ -1,4 +1,64 @@
 table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    note_aliases (id) {
+        id -> Int4,
+        note_id -> Int4,
+        alias -> Text,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    note_links (id) {
+        id -> Int4,
+        source_note_id -> Int4,
+        target_note_id -> Nullable<Int4>,
+        target_title -> Text,
+        explicit -> Bool,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    note_paths (note_id) {
+        note_id -> Int4,
+        path -> Ltree,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    note_revisions (id) {
+        id -> Int4,
+        note_id -> Int4,
+        title -> Varchar,
+        body -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    note_search (note_id) {
+        note_id -> Int4,
+        document -> Tsvector,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
     note_tags_id (id) {
         id -> Int4,
         note_id -> Int4,
@@ -7,6 +67,21 @@ table! {
 }
 
 table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    note_tombstones (id) {
+        id -> Int4,
+        note_id -> Int4,
+        user_id -> Int4,
+        deleted_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
     notes (id) {
         id -> Int4,
         title -> Varchar,
@@ -14,22 +89,32 @@ table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         user_id -> Int4,
-        parent_note_id -> Int4,
+        parent_note_id -> Nullable<Int4>,
         archived -> Bool,
         pinned -> Bool,
+        deleted_at -> Nullable<Timestamptz>,
+        version -> Int4,
+        position -> Nullable<Int4>,
     }
 }
 
 table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
     tags (id) {
         id -> Int4,
         tag -> Text,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        user_id -> Int4,
     }
 }
 
 table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
     users (id) {
         id -> Int4,
         name -> Varchar,
@@ -40,12 +125,23 @@ table! {
     }
 }
 
+joinable!(note_aliases -> notes (note_id));
+joinable!(note_paths -> notes (note_id));
+joinable!(note_revisions -> notes (note_id));
+joinable!(note_search -> notes (note_id));
 joinable!(note_tags_id -> notes (note_id));
 joinable!(note_tags_id -> tags (tag_id));
 joinable!(notes -> users (user_id));
+joinable!(tags -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    note_aliases,
+    note_links,
+    note_paths,
+    note_revisions,
+    note_search,
     note_tags_id,
+    note_tombstones,
     notes,
     tags,
     users,
@@ -0,0 +1,17 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// Postgres full text search document. Only ever built and queried in SQL.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "tsvector")]
+pub struct Tsvector;
+
+/// Postgres `ltree` label path. Only ever built and queried in SQL.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "ltree")]
+pub struct Ltree;
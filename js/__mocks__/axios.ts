@@ -18,6 +18,7 @@ import {
   SignInPayload,
   UpdateNotePayload,
   NewUserPayload,
+  DeletePreview,
 } from 'data/types';
 
 const testUser: User = {
@@ -39,7 +40,7 @@ export const emptyTestNote: NoteWithTags = {
   tags: [],
   archived: false,
   pinned: false,
-  parent_note_id: 0,
+  parent_note_id: null,
   created_at: '',
   updated_at: '',
 };
@@ -95,6 +96,7 @@ const NOT_AUTHORIZED = makeError(401, 'NotSignedIn');
 
 const noteEndpoint = /\/api\/secure\/notes\/(?<note_id>\d+)/;
 const tagsEndpoint = /\/api\/secure\/notes\/(?<note_id>\d+)\/tags/;
+const deletePreviewEndpoint = /\/api\/secure\/notes\/(?<note_id>\d+)\/delete-preview/;
 
 const getNoteDb = (user: User) => {
   if (!(user.id in noteDb)) {
@@ -138,7 +140,7 @@ const createNote = withUser((user: User, newNote: NewNotePayload): NoteWithTags
     id: newId,
     user_id: user.id,
     ...newNote,
-    parent_note_id: newNote.parent_note_id || 0,
+    parent_note_id: newNote.parent_note_id || null,
   });
   return notes[newId];
 });
@@ -148,6 +150,15 @@ const deleteNote = withUser((user: User, noteId: number) => {
   delete notes[noteId];
 });
 
+const deletePreview = withUser((user: User, noteId: number): DeletePreview => {
+  const notes = getNoteDb(user);
+  if (noteId in notes) {
+    const tagLinks = notes[noteId].tags.map((tag) => ({ note_id: noteId, tag }));
+    return { notes: [notes[noteId]], tag_links: tagLinks, inbound_links: [], token: 'token' };
+  }
+  throw NOT_FOUND;
+});
+
 const setTags = withUser((user: User, noteId: number, tags: string[]): NoteWithTags => {
   const notes = getNoteDb(user);
   if (noteId in notes) {
@@ -169,7 +180,7 @@ const createUser = (newUser: NewUserPayload): User => {
 export default {
   async get(url: string, config: AxiosRequestConfig): Promise<AxiosResponse> {
     if (url.endsWith('/api/secure/notes')) {
-      return makeResponse(listNotes(), config);
+      return makeResponse({ notes: listNotes() }, config);
     }
     if (url.endsWith('/api/get_user')) {
       if (currentUser) {
@@ -177,6 +188,11 @@ export default {
       }
       return makeResponse({}, config);
     }
+    const urlMatch = url.match(deletePreviewEndpoint);
+    if (urlMatch && urlMatch.groups) {
+      const noteId = parseInt(urlMatch.groups.note_id, 10);
+      return makeResponse(deletePreview(noteId), config);
+    }
     throw NOT_IMPLEMENTED;
   },
 
@@ -224,6 +240,9 @@ export default {
     const urlMatch = url.match(noteEndpoint);
     if (urlMatch && urlMatch.groups) {
       const noteId = parseInt(urlMatch.groups.note_id, 10);
+      if (config.params?.confirm !== 'token') {
+        throw makeError(412, 'StalePreview');
+      }
       return makeResponse(deleteNote(noteId), config);
     }
     throw NOT_IMPLEMENTED;
@@ -17,7 +17,7 @@ import {
 } from '@mui/material';
 
 interface Props extends DialogProps {
-  message?: string;
+  message?: React.ReactNode;
   negative?: string;
   positive?: string;
   onNegative?: () => void;
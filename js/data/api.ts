@@ -16,6 +16,8 @@ import {
   NewNotePayload,
   SignInPayload,
   NewUserPayload,
+  NotePage,
+  DeletePreview,
 } from './types';
 
 const api = '/api';
@@ -57,8 +59,12 @@ export default {
       return (await mapErr(axios.patch(`${noteRoot}s/${noteId}`, note))).data;
     },
 
-    async delete(noteId: number) {
-      return mapErr(axios.delete(`${noteRoot}s/${noteId}`));
+    async deletePreview(noteId: number): Promise<DeletePreview> {
+      return (await mapErr(axios.get(`${noteRoot}s/${noteId}/delete-preview`))).data;
+    },
+
+    async delete(noteId: number, token: string) {
+      return mapErr(axios.delete(`${noteRoot}s/${noteId}`, { params: { confirm: token } }));
     },
 
     async setTags(noteId: number, tags: string[]): Promise<NoteWithTags> {
@@ -66,7 +72,16 @@ export default {
     },
 
     async list(): Promise<NoteWithTags[]> {
-      return (await mapErr(axios.get('/api/secure/notes'))).data;
+      const notes: NoteWithTags[] = [];
+      let after: string | null | undefined;
+      do {
+        const page: NotePage = (
+          await mapErr(axios.get('/api/secure/notes', { params: { after } }))
+        ).data;
+        notes.push(...page.notes);
+        after = page.next;
+      } while (after);
+      return notes;
     },
   },
 
@@ -1,11 +1,97 @@
 // This file is auto-generated by tools/generate_types.js
 // Do not modify this file directly!
 
+export interface AliasPayload {
+  /**
+   * Surrounding whitespace is dropped.
+   */
+  alias: string;
+}
+
+export interface ArchiveSubtreePayload {
+  /**
+   * Only count what would change, without saving anything.
+   */
+  dry_run?: boolean;
+}
+
+export interface BrokenLink {
+  /**
+   * The note containing the link.
+   */
+  note_id: number;
+  note_title: string;
+  /**
+   * The link as written, e.g. `[[Strahd]]`, `/note/12` or `/disambiguation/12,40`.
+   */
+  link: string;
+  /**
+   * The note the link leads to, if there is one.
+   */
+  target_id?: number | null;
+  /**
+   * `missing` if nothing has that title, alias or id, or it is in the trash. `archived` if every note the link could lead to is archived.
+   */
+  reason: BrokenLinkReason;
+}
+
+export type BrokenLinkReason = "missing" | "archived";
+
+export interface DeletePreview {
+  /**
+   * The note and everything under it, parents first.
+   */
+  notes: NoteWithTags[];
+  tag_links: NoteTagLink[];
+  /**
+   * Links from the rest of the user's notes into the deleted ones, sorted by source title.
+   */
+  inbound_links: InboundLink[];
+  /**
+   * Pass to the delete endpoint as `confirm`. It stops matching once any of the above changes.
+   */
+  token: string;
+}
+
+export interface NoteTagLink {
+  note_id: number;
+  tag: string;
+}
+
+export interface InboundLink {
+  source_note_id: number;
+  source_title: string;
+  target_note_id?: number | null;
+  /**
+   * `true` for a `[[Title]]` link, `false` for a plain mention of the title.
+   */
+  explicit: boolean;
+}
+
+export interface DuplicateNotePayload {
+  /**
+   * Copy everything under the note as well.
+   */
+  deep?: boolean;
+  /**
+   * Give each copy the tags of its original.
+   */
+  tags?: boolean;
+  /**
+   * Where to put the copy. Left out, it goes next to the original. `null` puts it at the top level.
+   */
+  parent_note_id?: number | null;
+}
+
 export interface ErrorData {
   code: number;
   message: string;
   details: string;
   db?: DbErrorDetails | null;
+  /**
+   * The server's copy of a note that was changed by someone else, or that is already using the requested title.
+   */
+  note?: NoteWithTags | null;
 }
 
 export interface DbErrorDetails {
@@ -16,6 +102,71 @@ export interface DbErrorDetails {
   constraint_name?: string | null;
 }
 
+export interface NoteWithTags {
+  id: number;
+  title: string;
+  body: string;
+  tags: string[];
+  /**
+   * Other names the note can be linked and looked up by, sorted.
+   */
+  aliases: string[];
+  created_at: string;
+  updated_at: string;
+  user_id: number;
+  parent_note_id?: number | null;
+  archived: boolean;
+  pinned: boolean;
+  deleted_at?: string | null;
+  version: number;
+  /**
+   * Where the note sits among its siblings, once they have been reordered. Notes without one come after the rest.
+   */
+  position?: number | null;
+}
+
+export interface ExpandedNote {
+  note: NoteWithTags;
+  /**
+   * The body with every `![[Title]]` replaced by that note's body, itself expanded.
+   */
+  body: string;
+  /**
+   * The embeds that were left as written, in the order they appear. A note embedded more than once only reports its own embeds the first time.
+   */
+  unresolved: UnresolvedEmbed[];
+  /**
+   * Whether expansion stopped early because the body grew too large. The embeds it skipped are reported as `too_large`.
+   */
+  truncated: boolean;
+}
+
+export interface UnresolvedEmbed {
+  /**
+   * The note containing the embed, which may itself have been embedded.
+   */
+  note_id: number;
+  title: string;
+  reason: EmbedProblem;
+}
+
+export type EmbedProblem = "missing" | "ambiguous" | "cycle" | "too_deep" | "too_large";
+
+export interface MergeTagsPayload {
+  /**
+   * Tags to fold into `into`. They are deleted afterwards.
+   */
+  tags: number[];
+  into: number;
+}
+
+export interface MoveNotePayload {
+  /**
+   * `null` moves the note to the top level.
+   */
+  parent_note_id?: number | null;
+}
+
 export interface NewNotePayload {
   title: string;
   body: string;
@@ -28,17 +179,138 @@ export interface NewUserPayload {
   password: string;
 }
 
-export interface NoteWithTags {
+export interface NoteAlias {
   id: number;
+  note_id: number;
+  alias: string;
+}
+
+export interface NoteChanges {
+  notes: NoteWithTags[];
+  deleted: NoteTombstone[];
+  /**
+   * Pass back as `since` to fetch only what changed after this response. Changes from the minute before it are sent again.
+   */
+  cursor?: string | null;
+}
+
+export interface NoteTombstone {
+  id: number;
+  deleted_at: string;
+}
+
+export interface NoteLink {
+  /**
+   * The note on the other end of the link.
+   */
+  note: NoteWithTags;
+  /**
+   * `true` for a `[[Title]]` link, `false` for a plain mention of the title.
+   */
+  explicit: boolean;
+}
+
+export interface NoteListQuery {
+  archived?: boolean | null;
+  pinned?: boolean | null;
+  /**
+   * Also matches the tags nested under it.
+   */
+  tag?: string | null;
+  /**
+   * Only the direct children of this note.
+   */
+  parent?: number | null;
+  /**
+   * Only notes that are (or aren't) at the top level.
+   */
+  top_level?: boolean | null;
+  /**
+   * Only notes that are (or aren't) somewhere under an archived note.
+   */
+  under_archived?: boolean | null;
+  updated_since?: string | null;
+  /**
+   * Defaults to `position` when listing the children of a note, and `updated` otherwise.
+   */
+  sort?: NoteSort | null;
+  limit?: number | null;
+  /**
+   * The `next` cursor from the previous page.
+   */
+  after?: string | null;
+}
+
+export type NoteSort = "title" | "created" | "updated" | "position";
+
+export interface NotePage {
+  notes: NoteWithTags[];
+  /**
+   * Pass back as `after` to fetch the next page. Missing on the last page.
+   */
+  next?: string | null;
+}
+
+export interface NoteRename {
+  note: NoteWithTags;
+  /**
+   * The notes whose `[[links]]` to the old title were pointed at the new one, sorted by title.
+   */
+  rewritten: NoteWithTags[];
+}
+
+export interface NoteRevision {
+  id: number;
+  note_id: number;
   title: string;
   body: string;
-  tags: string[];
   created_at: string;
-  updated_at: string;
-  user_id: number;
-  parent_note_id: number;
-  archived: boolean;
-  pinned: boolean;
+}
+
+export interface NoteTree {
+  note: NoteWithTags;
+  /**
+   * In their manual order, then by title.
+   */
+  children: NoteTree[];
+}
+
+export interface RenameNotePayload {
+  title: string;
+  /**
+   * Only report what would change, without saving anything.
+   */
+  dry_run?: boolean;
+}
+
+export interface RenameTagPathPayload {
+  from: string;
+  to: string;
+}
+
+export interface RenameTagPayload {
+  tag: string;
+}
+
+export interface RevisionDiff {
+  from: number;
+  to: number;
+  title: DiffLine[];
+  body: DiffLine[];
+}
+
+export interface DiffLine {
+  op: DiffOp;
+  text: string;
+}
+
+export type DiffOp = "equal" | "insert" | "delete";
+
+export interface SearchResult {
+  note: NoteWithTags;
+  rank: number;
+  title_highlight: string;
+  body_highlight: string;
 }
 
 export interface SignInPayload {
@@ -46,9 +318,61 @@ export interface SignInPayload {
   password: string;
 }
 
+export interface SubtreeArchive {
+  note: NoteWithTags;
+  /**
+   * How many notes in the subtree, this one included, changed or would change.
+   */
+  changed: number;
+}
+
+/**
+ * A tag with everything nested under it. Counts include the notes tagged with any descendant.
+ */
+export interface TagNode {
+  /**
+   * The last segment of `tag`.
+   */
+  name: string;
+  tag: string;
+  /**
+   * Missing when no note uses this tag directly, only tags nested under it.
+   */
+  id?: number | null;
+  note_count: number;
+  last_used?: string | null;
+  children: TagNode[];
+}
+
+export interface TagSummary {
+  id: number;
+  tag: string;
+  /**
+   * How many notes outside the trash carry this tag.
+   */
+  note_count: number;
+  /**
+   * When a note with this tag was last changed.
+   */
+  last_used?: string | null;
+}
+
+export type TitleLookup =
+  | {
+      kind: "note";
+      note: NoteWithTags;
+    }
+  | {
+      kind: "disambiguation";
+      notes: NoteWithTags[];
+    };
+
 export interface UpdateNotePayload {
   title?: string | null;
   body?: string | null;
+  /**
+   * `null` moves the note to the top level.
+   */
   parent_note_id?: number | null;
   archived?: boolean | null;
   pinned?: boolean | null;
@@ -65,7 +65,7 @@ describe('<NoteEditor />', () => {
         ",
               "created_at": "",
               "id": 1,
-              "parent_note_id": 0,
+              "parent_note_id": null,
               "pinned": false,
               "tags": Array [
                 "tag1",
@@ -20,7 +20,7 @@ describe('<NoteList />', () => {
     const store = createStore();
     await store.dispatch(signInUser({ email: 'test@test.com', password: 'pass' }));
     expect(store.getState().notes.ids).toHaveLength(4);
-    const { getByText } = render(<NoteList noteViewFilter={null} parent_note_id={0} depth={1} />, {
+    const { getByText } = render(<NoteList noteViewFilter={null} parent_note_id={null} depth={1} />, {
       store,
     });
 
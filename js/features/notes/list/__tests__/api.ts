@@ -87,14 +87,15 @@ describe('deleteNotes', () => {
   });
 
   it('handles success well', async () => {
-    jest.spyOn(api.note, 'delete').mockResolvedValueOnce(undefined as any);
-    await store.dispatch(notesApi.deleteNote(note.id));
+    const remove = jest.spyOn(api.note, 'delete').mockResolvedValueOnce(undefined as any);
+    await store.dispatch(notesApi.deleteNote({ id: note.id, token: 'token' }));
+    expect(remove).toHaveBeenCalledWith(note.id, 'token');
     expect(store.getState().notes.entities).toEqual({});
   });
 
   it('handles failure well', async () => {
     jest.spyOn(api.note, 'delete').mockRejectedValueOnce({ code: 401, error: 'NotSignedIn' });
-    await store.dispatch(notesApi.deleteNote(note.id));
+    await store.dispatch(notesApi.deleteNote({ id: note.id, token: 'token' }));
     expect(store.getState().notes.entities).toEqual({
       [note.id]: note,
     });
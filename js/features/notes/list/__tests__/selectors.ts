@@ -25,7 +25,7 @@ const baseNote: NoteWithTags = {
   title: '',
   body: 'body',
   tags: [],
-  parent_note_id: 0,
+  parent_note_id: null,
   updated_at: '',
   archived: false,
   created_at: '',
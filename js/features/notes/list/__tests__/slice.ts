@@ -33,7 +33,7 @@ describe('reducers::notes()', () => {
             "body": "body",
             "created_at": "",
             "id": 1,
-            "parent_note_id": 0,
+            "parent_note_id": null,
             "pinned": false,
             "tags": Array [],
             "title": "title",
@@ -57,7 +57,7 @@ describe('reducers::notes()', () => {
             "body": "body",
             "created_at": "",
             "id": 1,
-            "parent_note_id": 0,
+            "parent_note_id": null,
             "pinned": false,
             "tags": Array [],
             "title": "title 3",
@@ -81,7 +81,7 @@ describe('reducers::notes()', () => {
             "body": "body",
             "created_at": "",
             "id": 1,
-            "parent_note_id": 0,
+            "parent_note_id": null,
             "pinned": false,
             "tags": Array [],
             "title": "title 3",
@@ -93,7 +93,7 @@ describe('reducers::notes()', () => {
             "body": "body",
             "created_at": "",
             "id": 2,
-            "parent_note_id": 0,
+            "parent_note_id": null,
             "pinned": false,
             "tags": Array [],
             "title": "title 4",
@@ -108,7 +108,10 @@ describe('reducers::notes()', () => {
       }
     `);
 
-    state = notes(state, deleteNote.fulfilled(noteTwo.id, '', noteTwo.id));
+    state = notes(
+      state,
+      deleteNote.fulfilled(noteTwo.id, '', { id: noteTwo.id, token: 'token' }),
+    );
     expect(state).toMatchInlineSnapshot(`
       Object {
         "entities": Object {
@@ -117,7 +120,7 @@ describe('reducers::notes()', () => {
             "body": "body",
             "created_at": "",
             "id": 1,
-            "parent_note_id": 0,
+            "parent_note_id": null,
             "pinned": false,
             "tags": Array [],
             "title": "title 3",
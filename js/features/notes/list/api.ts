@@ -55,11 +55,22 @@ export const createNote = createAsyncThunk(
   },
 );
 
+export const getDeletePreview = createAsyncThunk(
+  name('deletePreview'),
+  async (noteId: number, { rejectWithValue }) => {
+    try {
+      return await api.note.deletePreview(noteId);
+    } catch (e) {
+      throw rejectWithValue(e);
+    }
+  },
+);
+
 export const deleteNote = createAsyncThunk(
   name('delete'),
-  async (noteId: number, { rejectWithValue }) => {
+  async ({ id: noteId, token }: { id: number; token: string }, { rejectWithValue }) => {
     try {
-      await api.note.delete(noteId);
+      await api.note.delete(noteId, token);
       return noteId;
     } catch (e) {
       throw rejectWithValue(e);
@@ -96,7 +96,7 @@ export const getHasArchivedChild = createSelector(getNoteEntities, (notes) => {
     hasArchivedChild[note.id] = hasArchivedChild[note.id] || arch;
     let currentNote = note;
     while (validParent(currentNote)) {
-      currentNote = notes[currentNote.parent_note_id]!;
+      currentNote = notes[currentNote.parent_note_id!]!;
       hasArchivedChild[currentNote.id] = hasArchivedChild[currentNote.id] || arch;
     }
   });
@@ -116,10 +116,10 @@ export const getSortedNoteIds = createSelector(getNoteEntities, (allNotes) => {
     let note = thisNote;
     while (validParent(note)) {
       map.set(
-        note.parent_note_id,
+        note.parent_note_id!,
         mostRecent(note.updated_at, map.get(note.parent_note_id || -1)!),
       );
-      note = allNotes[note.parent_note_id]!;
+      note = allNotes[note.parent_note_id!]!;
     }
   });
 
@@ -14,9 +14,10 @@ export const emptyTestNote: NoteWithTags = {
   title: 'The Note',
   body: 'The Body',
   tags: [],
+  aliases: [],
   archived: false,
   pinned: false,
-  parent_note_id: 0,
+  parent_note_id: null,
   created_at: '',
   updated_at: '',
 };
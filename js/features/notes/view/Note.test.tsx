@@ -12,7 +12,7 @@ import * as React from 'react';
 import { createStore } from 'features/redux/store';
 import { signInUser } from 'features/user/api';
 
-import { render, waitForElementToBeRemoved } from 'components/test-utils';
+import { render, waitFor, waitForElementToBeRemoved } from 'components/test-utils';
 
 import Note from './Note';
 
@@ -26,7 +26,7 @@ describe('<Note />', () => {
         "body": "The Body",
         "created_at": "",
         "id": 1,
-        "parent_note_id": 0,
+        "parent_note_id": null,
         "pinned": false,
         "tags": Array [
           "tag1",
@@ -63,4 +63,22 @@ describe('<Note />', () => {
 
     expect(store.getState().notes.entities[1]!.pinned).toBeFalsy();
   });
+
+  test('previews a delete before confirming it', async () => {
+    const store = createStore();
+    await store.dispatch(signInUser({ email: 'test@test.com', password: 'pass' }));
+    const { findByTestId, findByText } = render(
+      <Note note={store.getState().notes.entities[2]!} />,
+      { store },
+    );
+
+    userEvent.click(await findByTestId('MoreVertIcon'));
+    userEvent.click(await findByText('Delete Note'));
+    expect(await findByText('Tags that come off:')).toBeTruthy();
+    expect(await findByText('tag2 (Note 2)')).toBeTruthy();
+
+    userEvent.click(await findByText('Yes'));
+    await waitFor(() => expect(store.getState().notes.entities[2]).toBeUndefined());
+    expect(store.getState().errorTracking.any).toBeFalsy();
+  });
 });
@@ -29,16 +29,18 @@ import {
   ListItemIcon,
   Menu,
   MenuItem,
+  Typography,
 } from '@mui/material';
 
 import MarkdownViewer from 'features/markdown/Viewer';
 import NoteLoading from 'features/note_loading/NoteLoading';
+import { AppDispatch } from 'features/redux/store';
 
 import ConfirmationDialog from 'components/ConfirmationDialog';
-import { NoteWithTags } from 'data/types';
+import { DeletePreview, NoteWithTags } from 'data/types';
 
 import { setEditingNote } from '../edit/slice';
-import { deleteNote, updateNote } from '../list/api';
+import { deleteNote, getDeletePreview, updateNote } from '../list/api';
 import { getLinkIds } from '../list/selectors';
 
 import Tags from './Tags';
@@ -48,13 +50,72 @@ type Props = {
   children?: React.ReactElement | React.ReactElement[];
 };
 
+const titleOf = (preview: DeletePreview, id: number) =>
+  preview.notes.find((n) => n.id === id)?.title ?? '';
+
+const DeleteSummary = ({ preview }: { preview: DeletePreview }) => {
+  const [, ...descendants] = preview.notes;
+  const sources = [...new Set(preview.inbound_links.map((l) => l.source_title))];
+  return (
+    <>
+      {descendants.length > 0 && (
+        <>
+          <Typography>Everything under it goes too:</Typography>
+          <ul>
+            {descendants.map((n) => (
+              <li key={n.id}>{n.title}</li>
+            ))}
+          </ul>
+        </>
+      )}
+      {preview.tag_links.length > 0 && (
+        <>
+          <Typography>Tags that come off:</Typography>
+          <ul>
+            {preview.tag_links.map((l) => (
+              <li key={`${l.note_id}:${l.tag}`}>
+                {l.tag} ({titleOf(preview, l.note_id)})
+              </li>
+            ))}
+          </ul>
+        </>
+      )}
+      {sources.length > 0 && (
+        <>
+          <Typography>Notes that link here:</Typography>
+          <ul>
+            {sources.map((title) => (
+              <li key={title}>{title}</li>
+            ))}
+          </ul>
+        </>
+      )}
+      <Typography>Are you sure?</Typography>
+    </>
+  );
+};
+
 const Note = ({ note, children }: Props) => {
   const titles = useSelector(getLinkIds);
   const [moreMenuEl, setMoreMenuEl] = React.useState<HTMLElement | null>(null);
-  const [confirmDeleteOpen, setConfirmDeleteOpen] = React.useState(false);
-  const dispatch = useDispatch();
+  const [deletePreview, setDeletePreview] = React.useState<DeletePreview | null>(null);
+  const dispatch = useDispatch<AppDispatch>();
 
-  const doDelete = () => dispatch(deleteNote(note.id));
+  const startDelete = async () => {
+    setMoreMenuEl(null);
+
+    const result = await dispatch(getDeletePreview(note.id));
+    if (getDeletePreview.fulfilled.match(result)) {
+      setDeletePreview(result.payload);
+    }
+  };
+
+  const doDelete = () => {
+    if (deletePreview) {
+      dispatch(deleteNote({ id: note.id, token: deletePreview.token }));
+    }
+    setDeletePreview(null);
+  };
 
   const archiveNote = async () => {
     setMoreMenuEl(null);
@@ -211,7 +272,7 @@ const Note = ({ note, children }: Props) => {
             },
           })}
         />
-        <MenuItem onClick={() => setConfirmDeleteOpen(true)}>
+        <MenuItem onClick={startDelete}>
           <ListItemIcon>
             <DeleteIcon />
           </ListItemIcon>
@@ -227,10 +288,11 @@ const Note = ({ note, children }: Props) => {
         </MenuItem>
       </Menu>
       <ConfirmationDialog
-        open={confirmDeleteOpen}
+        open={Boolean(deletePreview)}
         title={`You are about to delete note: ${note.title}`}
+        message={deletePreview && <DeleteSummary preview={deletePreview} />}
         onPositive={doDelete}
-        onNegative={() => setConfirmDeleteOpen(false)}
+        onNegative={() => setDeletePreview(null)}
       />
       <NoteLoading id={note.id} />
       <CardContent
@@ -12,16 +12,25 @@ use noted_db::DbConnection;
 
 mod current_user;
 mod notes;
+mod revisions;
+mod tags;
+mod trash;
 mod user;
 
 use notes::NoteScopeExt;
+use revisions::RevisionScopeExt;
+use tags::TagScopeExt;
+use trash::TrashScopeExt;
 use user::UserScopeExt;
 
 pub fn scope(db: DbConnection) -> actix_web::Scope {
-    web::scope("/api")
-        .data(db)
-        .add_user_routes()
-        .service(web::scope("/secure").add_note_routes())
+    web::scope("/api").data(db).add_user_routes().service(
+        web::scope("/secure")
+            .add_note_routes()
+            .add_revision_routes()
+            .add_tag_routes()
+            .add_trash_routes(),
+    )
 }
 
 #[cfg(test)]
@@ -42,7 +51,8 @@ mod test {
     use http::HeaderValue;
     use noted_db::{
         models::{
-            NewNotePayload, NewUserPayload, NoteWithTags, SignInPayload, UpdateNotePayload, User,
+            DeletePreview, MoveNotePayload, NewNotePayload, NewUserPayload, NotePage, NoteRevision,
+            NoteWithTags, SignInPayload, TagNode, TagSummary, UpdateNotePayload, User,
         },
         DbConnection,
     };
@@ -273,14 +283,34 @@ mod test {
             .unwrap();
         }
 
-        let notes: Vec<NoteWithTags> = send(
+        let notes: NotePage = send(
             &mut svc,
             &mut cookies,
             test::TestRequest::get().uri("/api/secure/notes"),
         )
         .await
         .unwrap();
-        assert_eq!(notes.len(), 10);
+        assert_eq!(notes.notes.len(), 10);
+        assert_eq!(notes.next, None);
+
+        let mut pages = vec![];
+        let mut uri = "/api/secure/notes?sort=title&limit=4".to_owned();
+        loop {
+            let page: NotePage = send(&mut svc, &mut cookies, test::TestRequest::get().uri(&uri))
+                .await
+                .unwrap();
+            pages.push(page.notes.len());
+            match page.next {
+                Some(next) => {
+                    uri = format!(
+                        "/api/secure/notes?sort=title&limit=4&after={}",
+                        next.replace(' ', "%20")
+                    )
+                }
+                None => break,
+            }
+        }
+        assert_eq!(pages, vec![4, 4, 2]);
     }
 
     #[actix_rt::test]
@@ -327,6 +357,312 @@ mod test {
         .unwrap();
 
         assert_eq!(note.title, "New Title");
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::patch()
+                .uri(&format!("/api/secure/notes/{}", note.id))
+                .header(hyper::header::IF_MATCH, format!("\"{}\"", note.version))
+                .set_json(&UpdateNotePayload {
+                    body: Some("New Body".into()),
+                    ..UpdateNotePayload::default()
+                }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note.body, "New Body");
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::patch()
+                .uri(&format!("/api/secure/notes/{}", note.id))
+                .header(hyper::header::IF_MATCH, format!("\"{}\"", note.version - 1))
+                .set_json(&UpdateNotePayload {
+                    title: Some("Stale Title".into()),
+                    ..UpdateNotePayload::default()
+                }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 412);
+        let current = err.note.unwrap();
+        assert_eq!(current.title, "New Title");
+        assert_eq!(current.version, note.version);
+
+        let linker: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::put()
+                .uri("/api/secure/note")
+                .set_json(&NewNotePayload {
+                    title: "Linker".into(),
+                    body: "See [[new title|the note]].".into(),
+                    parent_note_id: None,
+                }),
+        )
+        .await
+        .unwrap();
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::patch()
+                .uri(&format!("/api/secure/notes/{}?rewrite_links=true", note.id))
+                .set_json(&UpdateNotePayload {
+                    title: Some("Newer Title".into()),
+                    pinned: Some(true),
+                    ..UpdateNotePayload::default()
+                }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note.title, "Newer Title");
+        assert!(note.pinned);
+        let linker: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri(&format!("/api/secure/notes/{}", linker.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(linker.body, "See [[Newer Title|the note]].");
+    }
+
+    #[actix_rt::test]
+    async fn test_restore_revision() {
+        let (mut svc, mut cookies) = setup(true).await;
+        send::<User, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri("/api/sign_in")
+                .set_json(&json!({
+                    "email": "test@test.com",
+                    "password": "pass"
+                })),
+        )
+        .await
+        .unwrap();
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::put()
+                .uri("/api/secure/note")
+                .set_json(&NewNotePayload {
+                    title: "Restored Note".into(),
+                    body: "first".into(),
+                    parent_note_id: None,
+                }),
+        )
+        .await
+        .unwrap();
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::patch()
+                .uri(&format!("/api/secure/notes/{}", note.id))
+                .set_json(&UpdateNotePayload {
+                    body: Some("second".into()),
+                    ..UpdateNotePayload::default()
+                }),
+        )
+        .await
+        .unwrap();
+        let revisions: Vec<NoteRevision> = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri(&format!("/api/secure/notes/{}/revisions", note.id)),
+        )
+        .await
+        .unwrap();
+        let first = revisions.last().unwrap();
+        assert_eq!(first.body, "first");
+        let restore = format!(
+            "/api/secure/notes/{}/revisions/{}/restore",
+            note.id, first.id
+        );
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri(&restore)
+                .header(hyper::header::IF_MATCH, format!("\"{}\"", note.version - 1)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 412);
+        assert_eq!(err.note.unwrap().body, "second");
+
+        let restored: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri(&restore)
+                .header(hyper::header::IF_MATCH, format!("\"{}\"", note.version)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored.body, "first");
+    }
+
+    #[actix_rt::test]
+    async fn test_move_note() {
+        let (mut svc, mut cookies) = setup(true).await;
+        send::<User, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri("/api/sign_in")
+                .set_json(&json!({
+                    "email": "test@test.com",
+                    "password": "pass"
+                })),
+        )
+        .await
+        .unwrap();
+
+        let mut notes = vec![];
+        for (title, parent_note_id) in &[("Inn", None), ("Waterdeep", None), ("Inn", Some(1))] {
+            let parent_note_id = parent_note_id.map(|i: usize| notes[i]);
+            let note: NoteWithTags = send(
+                &mut svc,
+                &mut cookies,
+                test::TestRequest::put()
+                    .uri("/api/secure/note")
+                    .set_json(&NewNotePayload {
+                        title: (*title).into(),
+                        body: "".into(),
+                        parent_note_id,
+                    }),
+            )
+            .await
+            .unwrap();
+            notes.push(note.id);
+        }
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri(&format!("/api/secure/notes/{}/move", notes[1]))
+                .set_json(&MoveNotePayload {
+                    parent_note_id: Some(notes[2]),
+                }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 400);
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri(&format!("/api/secure/notes/{}/move", notes[2]))
+                .set_json(&MoveNotePayload {
+                    parent_note_id: None,
+                }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 409);
+        assert_eq!(err.note.unwrap().id, notes[0]);
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri(&format!("/api/secure/notes/{}/move", notes[1]))
+                .set_json(&MoveNotePayload {
+                    parent_note_id: Some(notes[0]),
+                }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note.parent_note_id, Some(notes[0]));
+    }
+
+    #[actix_rt::test]
+    async fn test_note_by_path() {
+        let (mut svc, mut cookies) = setup(true).await;
+        send::<User, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri("/api/sign_in")
+                .set_json(&json!({
+                    "email": "test@test.com",
+                    "password": "pass"
+                })),
+        )
+        .await
+        .unwrap();
+
+        let mut parent_note_id = None;
+        for title in &["Sword Coast", "Waterdeep", "Docks/Harbor"] {
+            let note: NoteWithTags = send(
+                &mut svc,
+                &mut cookies,
+                test::TestRequest::put()
+                    .uri("/api/secure/note")
+                    .set_json(&NewNotePayload {
+                        title: (*title).into(),
+                        body: "".into(),
+                        parent_note_id,
+                    }),
+            )
+            .await
+            .unwrap();
+            parent_note_id = Some(note.id);
+        }
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get()
+                .uri("/api/secure/notes/by-path/Sword%20Coast/Waterdeep/Docks%2FHarbor/"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(Some(note.id), parent_note_id);
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri("/api/secure/notes/by-path/Sword%20Coast/links"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 404);
+        assert_eq!(
+            err.message,
+            "No note titled \"links\" at segment 2 of the path"
+        );
+
+        let sale: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::put()
+                .uri("/api/secure/note")
+                .set_json(&NewNotePayload {
+                    title: "Sale: 100% + tax, %41".into(),
+                    body: "".into(),
+                    parent_note_id: None,
+                }),
+        )
+        .await
+        .unwrap();
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get()
+                .uri("/api/secure/notes/by-path/Sale%3A%20100%25%20%2B%20tax%2C%20%2541"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note.id, sale.id);
     }
 
     #[actix_rt::test]
@@ -373,32 +709,281 @@ mod test {
         .await
         .unwrap();
 
-        let notes: Vec<NoteWithTags> = send(
+        let notes: NotePage = send(
             &mut svc,
             &mut cookies,
             test::TestRequest::get().uri("/api/secure/notes"),
         )
         .await
         .unwrap();
-        assert_eq!(notes.len(), 2);
+        assert_eq!(notes.notes.len(), 2);
 
-        let stat: ApiStatus = send(
+        let err = send::<ApiStatus, _, _, _>(
             &mut svc,
             &mut cookies,
             test::TestRequest::delete().uri(&format!("/api/secure/notes/{}", note.id)),
         )
         .await
+        .unwrap_err();
+        assert_eq!(err.code, 400);
+
+        let preview: DeletePreview = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri(&format!("/api/secure/notes/{}/delete-preview", note.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(preview.notes.len(), 1);
+
+        let err = send::<ApiStatus, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::delete()
+                .uri(&format!("/api/secure/notes/{}?confirm=stale", note.id)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 412);
+
+        let stat: ApiStatus = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::delete().uri(&format!(
+                "/api/secure/notes/{}?confirm={}",
+                note.id, preview.token
+            )),
+        )
+        .await
         .unwrap();
         assert_eq!(stat.status, "ok");
 
-        let notes: Vec<NoteWithTags> = send(
+        let notes: NotePage = send(
             &mut svc,
             &mut cookies,
             test::TestRequest::get().uri("/api/secure/notes"),
         )
         .await
         .unwrap();
-        assert_eq!(notes.len(), 1);
-        assert_eq!(notes[0].title, "Note to Keep");
+        assert_eq!(notes.notes.len(), 1);
+        assert_eq!(notes.notes[0].title, "Note to Keep");
+
+        let err = send::<ApiStatus, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::delete().uri(&format!(
+                "/api/secure/notes/{}?confirm={}",
+                note.id, preview.token
+            )),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 404);
+
+        let trash: Vec<NoteWithTags> = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri("/api/secure/trash"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].title, "Note to Delete");
+    }
+
+    #[actix_rt::test]
+    async fn test_other_users_notes() {
+        let (mut svc, mut cookies) = setup(true).await;
+        send::<User, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri("/api/sign_in")
+                .set_json(&json!({
+                    "email": "test@test.com",
+                    "password": "pass"
+                })),
+        )
+        .await
+        .unwrap();
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::put()
+                .uri("/api/secure/note")
+                .set_json(&NewNotePayload {
+                    title: "Secret".into(),
+                    body: "body".into(),
+                    parent_note_id: None,
+                }),
+        )
+        .await
+        .unwrap();
+
+        let mut other_cookies = CookieJar::default();
+        send::<User, _, _, _>(
+            &mut svc,
+            &mut other_cookies,
+            test::TestRequest::put()
+                .uri("/api/sign_up")
+                .set_json(&json!({
+                    "email": "other@test.com",
+                    "name": "Other",
+                    "password": "pass",
+                })),
+        )
+        .await
+        .unwrap();
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut other_cookies,
+            test::TestRequest::put()
+                .uri(&format!("/api/secure/notes/{}/tags", note.id))
+                .set_json(&json!(["stolen"])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 404);
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut other_cookies,
+            test::TestRequest::patch()
+                .uri(&format!("/api/secure/notes/{}", note.id))
+                .set_json(&UpdateNotePayload {
+                    title: Some("Pwned".into()),
+                    ..UpdateNotePayload::default()
+                }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 404);
+
+        let err = send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut other_cookies,
+            test::TestRequest::put()
+                .uri("/api/secure/note")
+                .set_json(&NewNotePayload {
+                    title: "Child".into(),
+                    body: "body".into(),
+                    parent_note_id: Some(note.id),
+                }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, 403);
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri(&format!("/api/secure/notes/{}", note.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note.title, "Secret");
+        assert!(note.tags.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_manage_tags() {
+        let (mut svc, mut cookies) = setup(true).await;
+        send::<User, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri("/api/sign_in")
+                .set_json(&json!({
+                    "email": "test@test.com",
+                    "password": "pass"
+                })),
+        )
+        .await
+        .unwrap();
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::put()
+                .uri("/api/secure/note")
+                .set_json(&NewNotePayload {
+                    title: "Durnan".into(),
+                    body: "body".into(),
+                    parent_note_id: None,
+                }),
+        )
+        .await
+        .unwrap();
+
+        send::<NoteWithTags, _, _, _>(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::put()
+                .uri(&format!("/api/secure/notes/{}/tags", note.id))
+                .set_json(&json!(["npc", "NPC", "tavern"])),
+        )
+        .await
+        .unwrap();
+
+        let tags: Vec<TagNode> = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri("/api/secure/tags"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tags.len(), 3);
+        assert!(tags.iter().all(|t| t.note_count == 1));
+        let id_of = |name: &str| tags.iter().find(|t| t.tag == name).unwrap().id.unwrap();
+
+        let tag: TagSummary = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::post()
+                .uri("/api/secure/tags/merge")
+                .set_json(&json!({ "tags": [id_of("NPC")], "into": id_of("npc") })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tag.tag, "npc");
+
+        let tag: TagSummary = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::patch()
+                .uri(&format!("/api/secure/tags/{}", id_of("tavern")))
+                .set_json(&json!({ "tag": "inn" })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(tag.tag, "inn");
+
+        let stat: ApiStatus = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::delete().uri(&format!("/api/secure/tags/{}", id_of("npc"))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stat.status, "ok");
+
+        let note: NoteWithTags = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri(&format!("/api/secure/notes/{}", note.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(note.tags, vec!["inn"]);
+        let notes: NotePage = send(
+            &mut svc,
+            &mut cookies,
+            test::TestRequest::get().uri("/api/secure/notes?tag=inn"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(notes.notes.len(), 1);
     }
 }
@@ -7,16 +7,48 @@
 // except according to those terms.
 //
 
-use actix_web::{delete, get, patch, put, web, HttpResponse};
+use actix_web::{
+    delete, get,
+    http::header::{EntityTag, Header, IfMatch, ETAG},
+    patch, post, put, web, HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::Connection;
 use noted_db::{
-    models::{NewNotePayload, UpdateNotePayload},
+    error::DbError,
+    models::{
+        AliasPayload, ArchiveSubtreePayload, DuplicateNotePayload, MoveNotePayload, NewNotePayload,
+        NoteListQuery, NoteWithTags, RenameNotePayload, UpdateNotePayload, MAX_EMBED_DEPTH,
+    },
     DbConnection,
 };
+use percent_encoding::percent_decode_str;
 use serde::Deserialize;
 use serde_json::json;
 
 use crate::{api::current_user::CurrentUser, error::NotedError};
 
+pub(super) fn note_response(note: &NoteWithTags) -> HttpResponse {
+    HttpResponse::Ok()
+        .set_header(ETAG, EntityTag::strong(note.version.to_string()))
+        .json(note)
+}
+
+/// The note version a client expects from its `If-Match` header, if it sent one.
+pub(super) fn if_match(req: &HttpRequest) -> Option<i32> {
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Items(tags)) => tags.first().map(|tag| {
+            // Versions start at 1, so weak or malformed tags never match.
+            if tag.weak {
+                0
+            } else {
+                tag.tag().parse().unwrap_or(0)
+            }
+        }),
+        _ => None,
+    }
+}
+
 pub trait NoteScopeExt {
     fn add_note_routes(self) -> Self;
 }
@@ -25,10 +57,33 @@ impl NoteScopeExt for actix_web::Scope {
     fn add_note_routes(self) -> Self {
         self.service(new_note)
             .service(list_notes)
+            // Must come before get_note, otherwise `changes`, `tree` and the rest are parsed as
+            // note ids.
+            .service(note_changes)
+            .service(note_tree)
+            .service(broken_links)
+            .service(lookup_title)
+            .service(autocomplete)
+            .service(note_by_path)
             .service(get_note)
             .service(update_note)
+            .service(move_note)
+            .service(rename_note)
+            .service(duplicate_note)
+            .service(archive_subtree)
+            .service(unarchive_subtree)
+            .service(delete_preview)
             .service(delete_note)
             .service(set_tags)
+            .service(reorder_children)
+            .service(note_links)
+            .service(note_backlinks)
+            .service(expanded_note)
+            .service(note_aliases)
+            .service(add_alias)
+            .service(update_alias)
+            .service(delete_alias)
+            .service(search_notes)
     }
 }
 
@@ -36,8 +91,107 @@ impl NoteScopeExt for actix_web::Scope {
 async fn list_notes(
     user: CurrentUser,
     db_pool: web::Data<DbConnection>,
+    query: web::Query<NoteListQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.list_notes_page(&query, &db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct ChangesQuery {
+    since: Option<DateTime<Utc>>,
+}
+
+#[get("/notes/changes")]
+async fn note_changes(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    query: web::Query<ChangesQuery>,
 ) -> Result<HttpResponse, NotedError> {
-    Ok(HttpResponse::Ok().json(user.list_notes(&db_pool.db()?)?))
+    Ok(HttpResponse::Ok().json(user.note_changes(query.since, &db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct TreeQuery {
+    root: Option<i32>,
+    depth: Option<i32>,
+}
+
+#[get("/notes/tree")]
+async fn note_tree(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    query: web::Query<TreeQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.note_tree(query.root, query.depth, &db_pool.db()?)?))
+}
+
+#[get("/notes/broken-links")]
+async fn broken_links(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.broken_links(&db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct LookupQuery {
+    title: String,
+}
+
+#[get("/notes/lookup")]
+async fn lookup_title(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    query: web::Query<LookupQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.lookup_title(&query.title, &db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct AutocompleteQuery {
+    prefix: String,
+    limit: Option<i64>,
+}
+
+#[get("/notes/autocomplete")]
+async fn autocomplete(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    query: web::Query<AutocompleteQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.autocomplete(
+        &query.prefix,
+        query.limit.unwrap_or(10),
+        &db_pool.db()?,
+    )?))
+}
+
+const BY_PATH: &str = "/notes/by-path/";
+
+#[get("/notes/by-path/{path:.*}")]
+async fn note_by_path(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+) -> Result<HttpResponse, NotedError> {
+    // The router decodes everything but `/` and `+`, which would make `%252F` and `%2F` the
+    // same. Splitting the raw path and decoding each segment once keeps them apart.
+    let raw = req.uri().path();
+    let raw = raw
+        .find(BY_PATH)
+        .map_or("", |start| &raw[start + BY_PATH.len()..]);
+    let titles = raw
+        .split('/')
+        .filter(|title| !title.is_empty())
+        .map(|title| {
+            percent_decode_str(title)
+                .decode_utf8()
+                .map(|title| title.into_owned())
+                .map_err(|_| DbError::BadRequest("Path segments must be UTF-8"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let titles = titles.iter().map(String::as_str).collect::<Vec<_>>();
+    Ok(note_response(&user.note_by_path(&titles, &db_pool.db()?)?))
 }
 
 #[put("/note")]
@@ -46,7 +200,7 @@ async fn new_note(
     db_pool: web::Data<DbConnection>,
     new_note: web::Json<NewNotePayload>,
 ) -> Result<HttpResponse, NotedError> {
-    Ok(HttpResponse::Ok().json(&user.new_note(&*new_note, &db_pool.db()?)?))
+    Ok(note_response(&user.new_note(&*new_note, &db_pool.db()?)?))
 }
 
 #[derive(Deserialize)]
@@ -60,17 +214,144 @@ async fn get_note(
     db_pool: web::Data<DbConnection>,
     note_id: web::Path<NoteId>,
 ) -> Result<HttpResponse, NotedError> {
-    Ok(HttpResponse::Ok().json(user.note(note_id.id, &db_pool.db()?)?))
+    Ok(note_response(&user.note(note_id.id, &db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct UpdateQuery {
+    /// Point the `[[links]]` to the old title at the new one, like `/rename` does.
+    #[serde(default)]
+    rewrite_links: bool,
 }
 
 #[patch("/notes/{id}")]
 async fn update_note(
+    req: HttpRequest,
     user: CurrentUser,
     db_pool: web::Data<DbConnection>,
     note_id: web::Path<NoteId>,
+    query: web::Query<UpdateQuery>,
     update_note: web::Json<UpdateNotePayload>,
 ) -> Result<HttpResponse, NotedError> {
-    Ok(HttpResponse::Ok().json(user.update_note(note_id.id, &*update_note, &db_pool.db()?)?))
+    let db = db_pool.db()?;
+    let note = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        if let (true, Some(title)) = (query.rewrite_links, &update_note.title) {
+            user.rename_note(
+                note_id.id,
+                &RenameNotePayload {
+                    title: title.clone(),
+                    dry_run: false,
+                },
+                &db,
+            )?;
+        }
+        user.update_note(note_id.id, &*update_note, &db)
+    })?;
+    Ok(note_response(&note))
+}
+
+#[post("/notes/{id}/move")]
+async fn move_note(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    payload: web::Json<MoveNotePayload>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let note = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        user.move_note(note_id.id, payload.parent_note_id, &db)
+    })?;
+    Ok(note_response(&note))
+}
+
+#[post("/notes/{id}/rename")]
+async fn rename_note(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    payload: web::Json<RenameNotePayload>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let renamed = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        user.rename_note(note_id.id, &*payload, &db)
+    })?;
+    Ok(HttpResponse::Ok().json(&renamed))
+}
+
+#[post("/notes/{id}/duplicate")]
+async fn duplicate_note(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    payload: web::Json<DuplicateNotePayload>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(note_response(&user.duplicate_note(
+        note_id.id,
+        &*payload,
+        &db_pool.db()?,
+    )?))
+}
+
+#[post("/notes/{id}/archive")]
+async fn archive_subtree(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    payload: web::Json<ArchiveSubtreePayload>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let archived = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        user.archive_subtree(note_id.id, true, &*payload, &db)
+    })?;
+    Ok(HttpResponse::Ok().json(&archived))
+}
+
+#[post("/notes/{id}/unarchive")]
+async fn unarchive_subtree(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    payload: web::Json<ArchiveSubtreePayload>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let unarchived = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        user.archive_subtree(note_id.id, false, &*payload, &db)
+    })?;
+    Ok(HttpResponse::Ok().json(&unarchived))
+}
+
+#[get("/notes/{id}/delete-preview")]
+async fn delete_preview(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.delete_preview(note_id.id, &db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct DeleteQuery {
+    /// The `token` from the delete preview.
+    confirm: Option<String>,
 }
 
 #[delete("/notes/{id}")]
@@ -78,17 +359,163 @@ async fn delete_note(
     user: CurrentUser,
     db_pool: web::Data<DbConnection>,
     note_id: web::Path<NoteId>,
+    query: web::Query<DeleteQuery>,
 ) -> Result<HttpResponse, NotedError> {
-    user.delete_note(note_id.id, &db_pool.db()?);
+    let token = query.confirm.as_deref().ok_or(DbError::BadRequest(
+        "Deleting a note needs the token from its delete preview",
+    ))?;
+    user.confirm_delete(note_id.id, token, &db_pool.db()?)?;
     Ok(HttpResponse::Ok().json(&json!({"status": "ok"})))
 }
 
 #[put("/notes/{id}/tags")]
 async fn set_tags(
+    req: HttpRequest,
     user: CurrentUser,
     db_pool: web::Data<DbConnection>,
     note_id: web::Path<NoteId>,
     tags: web::Json<Vec<String>>,
 ) -> Result<HttpResponse, NotedError> {
-    Ok(HttpResponse::Ok().json(user.set_note_tags(note_id.id, &*tags, &db_pool.db()?)?))
+    let db = db_pool.db()?;
+    let note = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        user.set_note_tags(note_id.id, &*tags, &db)
+    })?;
+    Ok(note_response(&note))
+}
+
+#[put("/notes/{id}/children")]
+async fn reorder_children(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    children: web::Json<Vec<i32>>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.reorder_children(note_id.id, &*children, &db_pool.db()?)?))
+}
+
+#[get("/notes/{id}/links")]
+async fn note_links(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.links(note_id.id, &db_pool.db()?)?))
+}
+
+#[get("/notes/{id}/backlinks")]
+async fn note_backlinks(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.backlinks(note_id.id, &db_pool.db()?)?))
+}
+
+#[derive(Deserialize)]
+struct ExpandQuery {
+    depth: Option<u32>,
+}
+
+#[get("/notes/{id}/expanded")]
+async fn expanded_note(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    query: web::Query<ExpandQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.expanded_note(
+        note_id.id,
+        query.depth.unwrap_or(MAX_EMBED_DEPTH),
+        &db_pool.db()?,
+    )?))
+}
+
+#[get("/notes/{id}/aliases")]
+async fn note_aliases(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.note_aliases(note_id.id, &db_pool.db()?)?))
+}
+
+#[post("/notes/{id}/aliases")]
+async fn add_alias(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    payload: web::Json<AliasPayload>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let alias = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(note_id.id, version, &db)?;
+        }
+        user.add_alias(note_id.id, &*payload, &db)
+    })?;
+    Ok(HttpResponse::Ok().json(&alias))
+}
+
+#[derive(Deserialize)]
+struct AliasId {
+    id: i32,
+    alias_id: i32,
+}
+
+#[patch("/notes/{id}/aliases/{alias_id}")]
+async fn update_alias(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    path: web::Path<AliasId>,
+    payload: web::Json<AliasPayload>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let alias = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(path.id, version, &db)?;
+        }
+        user.update_alias(path.id, path.alias_id, &*payload, &db)
+    })?;
+    Ok(HttpResponse::Ok().json(&alias))
+}
+
+#[delete("/notes/{id}/aliases/{alias_id}")]
+async fn delete_alias(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    path: web::Path<AliasId>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(path.id, version, &db)?;
+        }
+        user.delete_alias(path.id, path.alias_id, &db)
+    })?;
+    Ok(HttpResponse::Ok().json(&json!({"status": "ok"})))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<i64>,
+}
+
+#[get("/search")]
+async fn search_notes(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    query: web::Query<SearchQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.search_notes(
+        &query.q,
+        query.limit.unwrap_or(50),
+        &db_pool.db()?,
+    )?))
 }
@@ -0,0 +1,101 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use diesel::Connection;
+use noted_db::{error::DbError, DbConnection};
+use serde::Deserialize;
+
+use crate::{
+    api::{
+        current_user::CurrentUser,
+        notes::{if_match, note_response},
+    },
+    error::NotedError,
+};
+
+pub trait RevisionScopeExt {
+    fn add_revision_routes(self) -> Self;
+}
+
+impl RevisionScopeExt for actix_web::Scope {
+    fn add_revision_routes(self) -> Self {
+        self.service(list_revisions)
+            .service(get_revision)
+            .service(diff_revisions)
+            .service(restore_revision)
+    }
+}
+
+#[derive(Deserialize)]
+struct NoteId {
+    id: i32,
+}
+
+#[derive(Deserialize)]
+struct RevisionId {
+    id: i32,
+    revision_id: i32,
+}
+
+#[derive(Deserialize)]
+struct DiffQuery {
+    from: i32,
+    to: i32,
+}
+
+#[get("/notes/{id}/revisions")]
+async fn list_revisions(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.note_revisions(note_id.id, &db_pool.db()?)?))
+}
+
+#[get("/notes/{id}/revisions/{revision_id}")]
+async fn get_revision(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    path: web::Path<RevisionId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.note_revision(path.id, path.revision_id, &db_pool.db()?)?))
+}
+
+#[get("/notes/{id}/diff")]
+async fn diff_revisions(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+    query: web::Query<DiffQuery>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.diff_revisions(
+        note_id.id,
+        query.from,
+        query.to,
+        &db_pool.db()?,
+    )?))
+}
+
+#[post("/notes/{id}/revisions/{revision_id}/restore")]
+async fn restore_revision(
+    req: HttpRequest,
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    path: web::Path<RevisionId>,
+) -> Result<HttpResponse, NotedError> {
+    let db = db_pool.db()?;
+    let note = db.transaction::<_, DbError, _>(|| {
+        if let Some(version) = if_match(&req) {
+            user.check_note_version(path.id, version, &db)?;
+        }
+        user.restore_revision(path.id, path.revision_id, &db)
+    })?;
+    Ok(note_response(&note))
+}
@@ -0,0 +1,83 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+use actix_web::{delete, get, patch, post, web, HttpResponse};
+use noted_db::{
+    models::{MergeTagsPayload, RenameTagPathPayload, RenameTagPayload},
+    DbConnection,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{api::current_user::CurrentUser, error::NotedError};
+
+pub trait TagScopeExt {
+    fn add_tag_routes(self) -> Self;
+}
+
+impl TagScopeExt for actix_web::Scope {
+    fn add_tag_routes(self) -> Self {
+        self.service(list_tags)
+            .service(merge_tags)
+            .service(rename_tag_path)
+            .service(rename_tag)
+            .service(delete_tag)
+    }
+}
+
+#[derive(Deserialize)]
+struct TagId {
+    id: i32,
+}
+
+#[get("/tags")]
+async fn list_tags(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.tag_tree(&db_pool.db()?)?))
+}
+
+#[post("/tags/merge")]
+async fn merge_tags(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    merge: web::Json<MergeTagsPayload>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.merge_tags(&merge.tags, merge.into, &db_pool.db()?)?))
+}
+
+#[post("/tags/rename")]
+async fn rename_tag_path(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    rename: web::Json<RenameTagPathPayload>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.rename_tag_path(&rename.from, &rename.to, &db_pool.db()?)?))
+}
+
+#[patch("/tags/{id}")]
+async fn rename_tag(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    tag_id: web::Path<TagId>,
+    rename: web::Json<RenameTagPayload>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.rename_tag(tag_id.id, &rename.tag, &db_pool.db()?)?))
+}
+
+#[delete("/tags/{id}")]
+async fn delete_tag(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    tag_id: web::Path<TagId>,
+) -> Result<HttpResponse, NotedError> {
+    user.delete_tag(tag_id.id, &db_pool.db()?)?;
+    Ok(HttpResponse::Ok().json(&json!({"status": "ok"})))
+}
@@ -0,0 +1,59 @@
+// Copyright 2026 Zachary Bush.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use noted_db::DbConnection;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{api::current_user::CurrentUser, error::NotedError};
+
+pub trait TrashScopeExt {
+    fn add_trash_routes(self) -> Self;
+}
+
+impl TrashScopeExt for actix_web::Scope {
+    fn add_trash_routes(self) -> Self {
+        self.service(list_trash)
+            .service(restore_note)
+            .service(purge_note)
+    }
+}
+
+#[derive(Deserialize)]
+struct NoteId {
+    id: i32,
+}
+
+#[get("/trash")]
+async fn list_trash(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.trash(&db_pool.db()?)?))
+}
+
+#[post("/trash/{id}/restore")]
+async fn restore_note(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    Ok(HttpResponse::Ok().json(user.restore_note(note_id.id, &db_pool.db()?)?))
+}
+
+#[delete("/trash/{id}")]
+async fn purge_note(
+    user: CurrentUser,
+    db_pool: web::Data<DbConnection>,
+    note_id: web::Path<NoteId>,
+) -> Result<HttpResponse, NotedError> {
+    user.purge_note(note_id.id, &db_pool.db()?)?;
+    Ok(HttpResponse::Ok().json(&json!({"status": "ok"})))
+}
@@ -9,7 +9,12 @@
 
 use noted::error::ErrorData;
 use noted_db::models::{
-    NewNotePayload, NewUserPayload, NoteWithTags, SignInPayload, UpdateNotePayload, User,
+    AliasPayload, ArchiveSubtreePayload, BrokenLink, DeletePreview, DuplicateNotePayload,
+    ExpandedNote, MergeTagsPayload, MoveNotePayload, NewNotePayload, NewUserPayload, NoteAlias,
+    NoteChanges, NoteLink, NoteListQuery, NotePage, NoteRename, NoteRevision, NoteTree,
+    NoteWithTags, RenameNotePayload, RenameTagPathPayload, RenameTagPayload, RevisionDiff,
+    SearchResult, SignInPayload, SubtreeArchive, TagNode, TagSummary, TitleLookup,
+    UpdateNotePayload, User,
 };
 use schemars::schema_for;
 
@@ -37,11 +42,36 @@ fn main() -> Result<(), anyhow::Error> {
     }
 
     write_schema!(dir, NoteWithTags);
+    write_schema!(dir, NoteListQuery);
+    write_schema!(dir, NotePage);
     write_schema!(dir, NewNotePayload);
     write_schema!(dir, UpdateNotePayload);
+    write_schema!(dir, MoveNotePayload);
+    write_schema!(dir, RenameNotePayload);
+    write_schema!(dir, DuplicateNotePayload);
+    write_schema!(dir, DeletePreview);
+    write_schema!(dir, ArchiveSubtreePayload);
+    write_schema!(dir, SubtreeArchive);
+    write_schema!(dir, NoteAlias);
+    write_schema!(dir, AliasPayload);
+    write_schema!(dir, TitleLookup);
+    write_schema!(dir, NoteRename);
     write_schema!(dir, NewUserPayload);
     write_schema!(dir, SignInPayload);
     write_schema!(dir, User);
+    write_schema!(dir, SearchResult);
+    write_schema!(dir, NoteRevision);
+    write_schema!(dir, NoteTree);
+    write_schema!(dir, NoteChanges);
+    write_schema!(dir, NoteLink);
+    write_schema!(dir, BrokenLink);
+    write_schema!(dir, ExpandedNote);
+    write_schema!(dir, RevisionDiff);
+    write_schema!(dir, TagSummary);
+    write_schema!(dir, TagNode);
+    write_schema!(dir, RenameTagPayload);
+    write_schema!(dir, RenameTagPathPayload);
+    write_schema!(dir, MergeTagsPayload);
     write_schema!(dir, ErrorData);
 
     Ok(())
@@ -8,6 +8,7 @@
 
 use actix_web::{HttpResponse, ResponseError};
 use http::status::StatusCode;
+use noted_db::models::NoteWithTags;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -36,6 +37,11 @@ pub struct ErrorData {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub db: Option<DbErrorDetails>,
+
+    /// The server's copy of a note that was changed by someone else, or that is already using the
+    /// requested title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<NoteWithTags>,
 }
 
 #[derive(Error, Debug)]
@@ -89,6 +95,14 @@ impl ResponseError for NotedError {
             });
         }
 
+        if let DbError(
+            noted_db::error::DbError::VersionMismatch(ref current)
+            | noted_db::error::DbError::TitleConflict(ref current),
+        ) = *self
+        {
+            data.note = Some((**current).clone());
+        }
+
         HttpResponse::build(self.status_code()).json(&data)
     }
 }
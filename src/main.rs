@@ -131,7 +131,9 @@ mod test {
     use cookie::{Cookie, CookieJar};
     use http::HeaderValue;
     use noted::error::ErrorData;
-    use noted_db::models::{NewNotePayload, NoteWithTags, UpdateNotePayload, User};
+    use noted_db::models::{
+        DeletePreview, NewNotePayload, NotePage, NoteWithTags, UpdateNotePayload, User,
+    };
     use serde::Deserialize;
     use serde_json::json;
 
@@ -250,18 +252,29 @@ mod test {
         }
 
         async fn list_notes(&mut self) -> Result<Vec<NoteWithTags>, ErrorData> {
-            TestClient::handle_result(
+            TestClient::handle_result::<NotePage, _, _>(
                 &mut self.cookie_jar,
                 self.server.get("/api/secure/notes"),
                 &"",
             )
             .await
+            .map(|page| page.notes)
         }
 
         async fn delete_note(&mut self, id: i32) -> Result<ApiStatus, ErrorData> {
+            let preview: DeletePreview = TestClient::handle_result(
+                &mut self.cookie_jar,
+                self.server
+                    .get(format!("/api/secure/notes/{}/delete-preview", id)),
+                &"",
+            )
+            .await?;
             TestClient::handle_result(
                 &mut self.cookie_jar,
-                self.server.delete(format!("/api/secure/notes/{}", id)),
+                self.server.delete(format!(
+                    "/api/secure/notes/{}?confirm={}",
+                    id, preview.token
+                )),
                 &"",
             )
             .await